@@ -4,3 +4,4 @@ pub const SERVER_ACCOUNT_LEN: usize = 108;
 pub const MAX_SERVER_NUM: usize = 10;
 pub const PROFILE_ACCOUNT_LEN: usize = 130;
 pub const PROFILE_VERSION: u8 = 2;
+pub const TICKET_SEED: &str = "race-ticket";
@@ -266,6 +266,82 @@ pub enum ProcessError {
     /// 41
     #[error("Inconsistent credentials")]
     InconsistentCredentials,
+
+    /// 42
+    #[error("Game is paused")]
+    GamePaused,
+
+    /// 43
+    #[error("Game is not paused")]
+    GameNotPaused,
+
+    /// 44
+    #[error("Only ticket games that have never been settled can be cancelled")]
+    CantCancelGame,
+
+    /// 45
+    #[error("Only closed or cancelled games can be reopened")]
+    CantReopenGame,
+
+    /// 46
+    #[error("Invalid game schedule")]
+    InvalidSchedule,
+
+    /// 47
+    #[error("Registration is closed")]
+    RegistrationClosed,
+
+    /// 48
+    #[error("Rebuy limit exceeded")]
+    RebuyLimitExceeded,
+
+    /// 49
+    #[error("Invalid entry type")]
+    InvalidEntryType,
+
+    /// 4A
+    #[error("Player is not in the allowlist")]
+    NotInAllowlist,
+
+    /// 4B
+    #[error("Invalid invite code")]
+    InvalidInviteCode,
+
+    /// 4C
+    #[error("Join is not approved by the gatekeeper")]
+    JoinNotApproved,
+
+    /// 4D
+    #[error("Deposit exceeds the balance cap")]
+    BalanceCapExceeded,
+
+    /// 4E
+    #[error("Mint account is required for Token-2022 transfers")]
+    MintAccountRequired,
+
+    /// 4F
+    #[error("Entry is locked")]
+    EntryLocked,
+
+    /// 50
+    #[error("Player holds a balance or a pending deposit")]
+    PlayerNotKickable,
+
+    /// 51
+    #[error("Invalid tournament account")]
+    InvalidTournament,
+
+    /// 52
+    #[error("Invalid ticket account")]
+    InvalidTicket,
+
+    /// 53
+    #[error("The game still holds funds owed to players")]
+    OutstandingFunds,
+
+    /// 54
+    #[error("The ticket can still be redeemed")]
+    TicketNotExpired,
 }
 
 impl From<ProcessError> for ProgramError {
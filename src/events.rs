@@ -0,0 +1,233 @@
+//! Structured events for indexers.
+//!
+//! Every event is a Borsh-encoded [`RaceEvent`] logged with `sol_log_data`, which appears in the
+//! transaction logs as `Program data: <base64>`.  Indexers decode the base64 payload and pass the
+//! bytes to [`decode`].
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{entrypoint::ProgramResult, log::sol_log_data, pubkey::Pubkey};
+
+use crate::types::VoteType;
+
+/// The balance change and the payout of a player in a settlement.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct PlayerDelta {
+    pub player_id: u64,
+    // positive for an increase, negative for a decrease
+    pub change: i128,
+    // the amount paid out to the player
+    pub amount: u64,
+    pub eject: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum RaceEvent {
+    PlayerJoined {
+        game: Pubkey,
+        player: Pubkey,
+        position: u16,
+        access_version: u64,
+        amount: u64,
+    },
+    Deposited {
+        game: Pubkey,
+        player: Pubkey,
+        access_version: u64,
+        amount: u64,
+    },
+    Settled {
+        game: Pubkey,
+        settle_version: u64,
+        deltas: Vec<PlayerDelta>,
+    },
+    Rejected {
+        game: Pubkey,
+        player: Pubkey,
+        access_version: u64,
+        amount: u64,
+    },
+    BonusAwarded {
+        game: Pubkey,
+        player: Pubkey,
+        identifier: String,
+        token: Pubkey,
+        amount: u64,
+    },
+    RecipientClaimed {
+        recipient: Pubkey,
+        slot_id: u8,
+        claimer: Pubkey,
+        amount: u64,
+    },
+    ServerJoined {
+        game: Pubkey,
+        server: Pubkey,
+        access_version: u64,
+    },
+    GameClosed {
+        game: Pubkey,
+    },
+    TicketAwarded {
+        game: Pubkey,
+        player: Pubkey,
+        // the game to join with the ticket
+        target_game: Pubkey,
+        amount: u64,
+    },
+    GameCancelled {
+        game: Pubkey,
+    },
+    PlayerKicked {
+        game: Pubkey,
+        player: Pubkey,
+        access_version: u64,
+    },
+    PlayerMoved {
+        game: Pubkey,
+        // the game where the player is seated now
+        dest_game: Pubkey,
+        player: Pubkey,
+        position: u16,
+        access_version: u64,
+        amount: u64,
+    },
+    TournamentPrizePaid {
+        tournament: Pubkey,
+        player: Pubkey,
+        amount: u64,
+    },
+    TicketRefunded {
+        game: Pubkey,
+        player: Pubkey,
+        amount: u64,
+    },
+    TicketTokenRefunded {
+        game: Pubkey,
+        holder: Pubkey,
+        amount: u64,
+    },
+    FinalGameSet {
+        tournament: Pubkey,
+        game: Pubkey,
+    },
+    TournamentClosed {
+        tournament: Pubkey,
+        // the rest of the prize pool withdrawn by the owner
+        amount: u64,
+    },
+    GameCreated {
+        game: Pubkey,
+        owner: Pubkey,
+    },
+    GameReopened {
+        game: Pubkey,
+        // the versions the new session starts with
+        access_version: u64,
+        settle_version: u64,
+    },
+    GamePaused {
+        game: Pubkey,
+    },
+    GameResumed {
+        game: Pubkey,
+    },
+    GameSponsored {
+        game: Pubkey,
+        sponsor: Pubkey,
+        amount: u64,
+    },
+    TicketMintSet {
+        game: Pubkey,
+        ticket_mint: Pubkey,
+    },
+    TicketBought {
+        game: Pubkey,
+        buyer: Pubkey,
+        // the value of the token, received by the stake account
+        amount: u64,
+    },
+    SeatChanged {
+        game: Pubkey,
+        player: Pubkey,
+        position: u16,
+        access_version: u64,
+    },
+    SeatsSwapped {
+        game: Pubkey,
+        player_id: u64,
+        other_player_id: u64,
+        access_version: u64,
+    },
+    PlayersRegCompacted {
+        game: Pubkey,
+        // the rent returned to the owner
+        refund: u64,
+    },
+    PlayersRegMigrated {
+        game: Pubkey,
+    },
+    AllowlistUpdated {
+        game: Pubkey,
+        merkle_root: [u8; 32],
+    },
+    GatekeeperSet {
+        game: Pubkey,
+        gatekeeper: Option<Pubkey>,
+    },
+    BonusAttached {
+        game: Pubkey,
+        identifier: String,
+        token: Pubkey,
+        amount: u64,
+    },
+    Voted {
+        game: Pubkey,
+        voter: Pubkey,
+        votee: Pubkey,
+        vote_type: VoteType,
+    },
+    TournamentCreated {
+        tournament: Pubkey,
+        owner: Pubkey,
+    },
+    TournamentGameAdded {
+        tournament: Pubkey,
+        game: Pubkey,
+    },
+}
+
+impl RaceEvent {
+    /// Log the event with `sol_log_data`.
+    pub fn emit(&self) -> ProgramResult {
+        let data = borsh::to_vec(self)?;
+        sol_log_data(&[&data]);
+        Ok(())
+    }
+}
+
+/// Decode the bytes of a `Program data` log into an event.
+pub fn decode(data: &[u8]) -> Result<RaceEvent, borsh::io::Error> {
+    RaceEvent::try_from_slice(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let event = RaceEvent::Settled {
+            game: Pubkey::new_unique(),
+            settle_version: 3,
+            deltas: vec![PlayerDelta {
+                player_id: 1,
+                change: -100,
+                amount: 50,
+                eject: true,
+            }],
+        };
+        let data = borsh::to_vec(&event).unwrap();
+        assert_eq!(decode(&data).unwrap(), event);
+        assert!(decode(&data[..data.len() - 1]).is_err());
+    }
+}
@@ -1,5 +1,5 @@
 use crate::types::{
-    AssignRecipientParams, AttachBonusParams, CreateGameAccountParams, CreatePlayerProfileParams, CreateRecipientParams, CreateRegistrationParams, DepositParams, JoinParams, PublishParams, RecipientSlotInit, RegisterServerParams, RejectDepositsParams, ServeParams, SettleParams, VoteParams
+    AssignRecipientParams, AttachBonusParams, BatchJoinParams, ChangeSeatParams, CreateGameAccountParams, CreatePlayerProfileParams, CreateRecipientParams, CreateRegistrationParams, CreateTournamentParams, DepositParams, JoinParams, KickPlayerParams, MoveBalanceParams, PublishParams, RecipientSlotInit, RegisterServerParams, RejectDepositsParams, ServeParams, SetGatekeeperParams, SettleParams, SettleTournamentParams, SponsorGameParams, SwapSeatsParams, UpdateAllowlistParams, VoteParams
 };
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::program_error::ProgramError;
@@ -14,7 +14,7 @@ pub enum RaceInstruction {
     /// 2. `[writable]` The players account, hold all player registrations
     /// 3. `[writable]` The temp stake account
     /// 4. `[]` The mint account
-    /// 5. `[]` The token program
+    /// 5. `[]` The token program, either SPL token or Token-2022
     /// 6. `[]` The bundled data account
     /// 7. `[]` The recipient account
     /// 8. `[]` The system program
@@ -31,7 +31,10 @@ pub enum RaceInstruction {
     /// 5. `[]` The account to receive tokens
     /// 6. `[]` Token program
     /// 7. `[]` The system program
-    /// Rest are the bonus stake account and receiver(owner)'s ATA
+    /// (Optional)8. `[]` The mint account. For Token-2022 tokens
+    /// (Optional)9. `[]` The ticket mint account. For games selling ticket tokens
+    /// Rest are the bonus stake account and receiver(owner)'s ATA, followed by the bonus mint
+    /// account for Token-2022 bonuses
     CloseGameAccount,
 
     /// # [2] Create an on-chain "lobby" for game registration
@@ -69,10 +72,15 @@ pub enum RaceInstruction {
     /// 5. `[]` The recipient account
     /// 6. `[]` The token program
     /// 7. `[]` The system program
+    /// (Optional)8. `[]` The mint account. For Token-2022 tokens
     /// Following:
     /// `[]` Every players' account to get paid, must be in the same order with payment settles
     /// `[]` Every recipient slot accounts to receive transfer
-    /// `[]` Every bonus account and the receiver account to receive bonus
+    /// `[]` Every bonus account and the receiver account to receive bonus, followed by the bonus
+    ///     mint account for Token-2022 bonuses
+    /// `[writable]` For every ticket award, the target game account, the target stake account and
+    ///     the ticket account (PDA of the ticket seed, the target game and the player), followed
+    ///     by the tournament account for tournament games
     Settle { params: SettleParams },
 
     /// # [6] Vote
@@ -115,17 +123,29 @@ pub enum RaceInstruction {
     ///
     /// Accounts expected:
     /// 0. `[signer]` The payer account
-    /// 1. `[]` The player account
-    /// 2. `[writable]` The temp account
+    /// 1. `[]` The player account to own the seat and receive payouts, may be the payer itself
+    /// 2. `[writable]` The temp account holding exactly the amount, or the payer's ATA (the payer
+    ///     itself for SOL) to pay directly, or the ticket account awarded to the player by a
+    ///     satellite, which is closed to its rent payer, or the payer's token account of ticket tokens,
+    ///     one of which is burned
     /// 3. `[writable]` The game account
     /// 4. `[writable]` The players reg account
     /// 5. `[]` The mint account.
     /// 6. `[writable]` The stake account that holds players' buyin assets
     /// 7. `[]` The recipient account
     /// 8. `[writable]` The pda account
-    /// 9. `[]` The SPL token program
+    /// 9. `[]` The SPL token program, the one of the ticket token account to join with a ticket token
     /// 10. `[]` The system program
-    /// (Optional)11. `[]` Other account to receive the payment. For EntryType::Ticket
+    /// (Optional)11. `[writable]` The recipient slot stake account to receive the fee. For EntryType::Ticket with a fee
+    /// Rest are optional accounts, in order, only when required by the game:
+    /// `[signer]` The gatekeeper, or `[]` the instructions sysvar when the gatekeeper signs the
+    ///     game and player addresses with an ed25519 instruction. For games with gatekeeper
+    /// `[]` The instructions sysvar, with an ed25519 instruction where the invite key signs the game
+    ///     and player addresses. For games with invite code
+    /// `[writable]` The tournament account. For tournament games, whose stake account (6) is the
+    ///     tournament stake account
+    /// `[writable]` The ticket mint account. For joins paid with a ticket token
+    /// `[writable]` The rent payer of the ticket account. For joins with a satellite ticket
     JoinGame { params: JoinParams },
 
     /// # [11] Publish a game
@@ -150,7 +170,7 @@ pub enum RaceInstruction {
     /// 2. `[]` The recipient account
     /// 3. `[]` The token program
     /// 4. `[]` The system program
-    /// 4+n. `[]` The Nth staking account for slots
+    /// 4+n. `[]` The Nth staking account for slots, followed by its mint account for Token-2022 tokens
     CreateRecipient { params: Box<CreateRecipientParams> },
 
     /// # [13] Assign recipient
@@ -173,14 +193,16 @@ pub enum RaceInstruction {
     /// `[]` The PDA account as the owner of the stake account
     /// `[writable]` The stake account
     /// `[writable]` ATA to receive tokens
+    /// `[]` The mint account, only for Token-2022 slots
     RecipientClaim,
 
     /// # [15] Deposit tokens to a game
     ///
     /// Accounts expected:
     /// 0. `[signer]` The payer account
-    /// 1. `[]` The player account
-    /// 2. `[writable]` The temp account
+    /// 1. `[]` The player account to own the seat and receive payouts, may be the payer itself
+    /// 2. `[writable]` The temp account holding exactly the amount, or the payer's ATA (the payer
+    ///     itself for SOL) to pay directly
     /// 3. `[writable]` The game account
     /// 4. `[writable]` The players reg account
     /// 5. `[]` The mint account
@@ -188,6 +210,10 @@ pub enum RaceInstruction {
     /// 7. `[writable]` The pda account
     /// 8. `[]` The SPL token program
     /// 9. `[]` The system program
+    /// (Optional)10. `[]` The recipient account. For EntryType::Ticket with a fee
+    /// (Optional)11. `[writable]` The recipient slot stake account to receive the fee
+    /// (Optional)Last. `[writable]` The tournament account. For tournament games, whose stake
+    ///     account (6) is the tournament stake account
     Deposit { params: DepositParams },
 
     /// # [16] Attach a bonus to a game
@@ -197,7 +223,8 @@ pub enum RaceInstruction {
     /// 1. `[writable]` The game account
     /// 2. `[]` The SPL token program
     /// 3. `[]` The system program
-    /// Rest. `[writable]` The temp account for each bonuses
+    /// Rest. `[writable]` The temp account for each bonuses, followed by its mint account for
+    ///     Token-2022 tokens
     AttachBonus { params: AttachBonusParams },
 
     /// #[17] Reject a deposit
@@ -206,10 +233,12 @@ pub enum RaceInstruction {
     /// 0. `[signer]` The transactor account
     /// 1. `[writable]` The game account
     /// 2. `[writable]` The players reg account
-    /// 3. `[]` The stake account
-    /// 4. `[]` The PDA from game account
+    /// 3. `[]` The stake account, the tournament stake account for tournament games
+    /// 4. `[]` The PDA from game account, from the tournament account for tournament games
     /// 5. `[]` The SPL token program
     /// 6. `[]` The system program
+    /// (Optional)7. `[]` The mint account. For Token-2022 tokens
+    /// (Optional)8. `[writable]` The tournament account. For tournament games
     /// Rest. `[]` The receiver for each rejected deposit
     RejectDeposits { params: RejectDepositsParams },
 
@@ -221,7 +250,289 @@ pub enum RaceInstruction {
     /// 2. `[]` The staking account for slots
     /// 3. `[]` The SPL token program
     /// 4. `[]` The system program
-    AddRecipientSlot { params: RecipientSlotInit }
+    /// (Optional)5. `[]` The mint account. For Token-2022 tokens
+    AddRecipientSlot { params: RecipientSlotInit },
+
+    /// #[19] Pause a game
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The game owner
+    /// 1. `[writable]` The game account
+    /// 2. `[]` The system program
+    PauseGame,
+
+    /// #[20] Resume a paused game
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The game owner
+    /// 1. `[writable]` The game account
+    /// 2. `[]` The system program
+    ResumeGame,
+
+    /// #[21] Cancel a ticket game before its first settlement, or refund the deposits left by a
+    /// previous cancellation
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The game owner or the transactor
+    /// 1. `[writable]` The game account
+    /// 2. `[writable]` The players reg account
+    /// 3. `[writable]` The stake account, the tournament stake account for tournament games
+    /// 4. `[]` The PDA from game account, from the tournament account for tournament games
+    /// 5. `[]` The SPL token program
+    /// 6. `[]` The system program
+    /// 7. `[writable]` The game owner, receives the rent of the removed players
+    /// (Optional)8. `[]` The mint account. For Token-2022 tokens
+    /// (Optional)9. `[]` The recipient account. For tickets with a fee
+    /// (Optional)10. `[]` The PDA of the recipient slot for game token. For tickets with a fee
+    /// (Optional)11. `[writable]` The stake account of the recipient slot. For tickets with a fee
+    /// (Optional)12. `[writable]` The tournament account. For tournament games
+    /// Rest. `[writable]` The receiver for each pending or rejected deposit, in order
+    CancelGame,
+
+    /// #[22] Reopen a closed or cancelled game with fresh parameters
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The game owner
+    /// 1. `[writable]` The game account
+    /// 2. `[writable]` The players account, hold all player registrations
+    /// 3. `[writable]` The stake account
+    /// 4. `[]` The mint account
+    /// 5. `[]` The token program
+    /// 6. `[]` The bundled data account
+    /// 7. `[]` The recipient account
+    /// 8. `[]` The system program
+    ReopenGame { params: CreateGameAccountParams },
+
+    /// #[23] Update the Merkle root of a game with allowlist entry
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The game owner
+    /// 1. `[writable]` The game account
+    /// 2. `[]` The system program
+    UpdateAllowlist { params: UpdateAllowlistParams },
+
+    /// #[24] Set or remove the gatekeeper who approves joins
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The game owner
+    /// 1. `[writable]` The game account
+    /// 2. `[]` The system program
+    SetGatekeeper { params: SetGatekeeperParams },
+
+    /// #[25] Add sponsor funds to the stake account of a game
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The game owner
+    /// 1. `[writable]` The token account to pay from, ignored for SOL
+    /// 2. `[writable]` The game account
+    /// 3. `[writable]` The stake account
+    /// 4. `[]` The SPL token program
+    /// 5. `[]` The system program
+    /// (Optional)6. `[]` The mint account. For Token-2022 tokens
+    SponsorGame { params: SponsorGameParams },
+
+    /// #[26] Seat many players at once, paid from a single account
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The game owner or the transactor
+    /// 1. `[writable]` The token account to pay from, the signer itself for SOL
+    /// 2. `[writable]` The game account
+    /// 3. `[writable]` The players reg account
+    /// 4. `[]` The mint account
+    /// 5. `[writable]` The stake account
+    /// 6. `[]` The token program
+    /// 7. `[]` The system program
+    /// (Optional)8. `[]` The recipient account. For EntryType::Ticket with a fee
+    /// (Optional)9. `[writable]` The recipient slot stake account to receive the fee
+    /// Rest are optional accounts, in order, only when the transactor signs and the game requires:
+    /// `[signer]` The gatekeeper, or `[]` the instructions sysvar with an ed25519 instruction of
+    ///     the gatekeeper for each player. For games with gatekeeper
+    /// `[]` The instructions sysvar, with an ed25519 instruction of the invite key for each
+    ///     player. For games with invite code
+    /// (Optional)Last. `[writable]` The tournament account. For tournament games, whose stake
+    ///     account (5) is the tournament stake account
+    BatchJoin { params: BatchJoinParams },
+
+    /// #[27] Migrate a legacy players reg account to the versioned layout
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The game owner or the transactor
+    /// 1. `[]` The game account
+    /// 2. `[writable]` The players reg account
+    /// 3. `[]` The system program
+    MigratePlayersReg,
+
+    /// #[28] Drop the empty slots of the players reg account, refund the rent to the game owner
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The fee payer, anyone
+    /// 1. `[]` The game account
+    /// 2. `[writable]` The players reg account
+    /// 3. `[writable]` The game owner account to receive the refund
+    CompactPlayersReg,
+
+    /// #[29] Move the player to an empty seat, when the entry lock allows joining
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The player account
+    /// 1. `[writable]` The game account
+    /// 2. `[writable]` The players reg account
+    /// 3. `[]` The system program
+    ChangeSeat { params: ChangeSeatParams },
+
+    /// #[30] Swap the seats of two players
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The transactor account
+    /// 1. `[writable]` The game account
+    /// 2. `[writable]` The players reg account
+    /// 3. `[]` The system program
+    SwapSeats { params: SwapSeatsParams },
+
+    /// #[31] Remove a player who holds no balance and no pending deposit
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The game owner or the transactor
+    /// 1. `[writable]` The game account
+    /// 2. `[writable]` The players reg account
+    /// 3. `[]` The system program
+    /// (Optional)4. `[writable]` The tournament account. For tournament games
+    KickPlayer { params: KickPlayerParams },
+
+    /// #[32] Move a player with its balance to another game of the same tournament, or to a cash
+    /// game of the same owner and token as a pending deposit
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The transactor of both games
+    /// 1. `[writable]` The source game account
+    /// 2. `[writable]` The source players reg account
+    /// 3. `[writable]` The source stake account
+    /// 4. `[]` The PDA account of the source game
+    /// 5. `[writable]` The destination game account
+    /// 6. `[writable]` The destination players reg account
+    /// 7. `[writable]` The destination stake account
+    /// 8. `[]` The token program
+    /// 9. `[]` The system program
+    /// (Optional)10. `[]` The mint account, required for Token-2022
+    /// (Optional)Last. `[writable]` The tournament account. For tournament games
+    MoveBalance { params: MoveBalanceParams },
+
+    /// #[33] Create a tournament to group several games under one prize pool
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The owner of the tournament
+    /// 1. `[writable]` The tournament account, owned by the program
+    /// 2. `[writable]` The stake account, the PDA of the tournament for SOL
+    /// 3. `[]` The mint account
+    /// 4. `[]` The token program
+    /// 5. `[]` The system program
+    CreateTournament { params: CreateTournamentParams },
+
+    /// #[34] Link a game without players to a tournament
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The owner of both the tournament and the game
+    /// 1. `[writable]` The tournament account
+    /// 2. `[writable]` The game account
+    /// 3. `[]` The players reg account
+    /// 4. `[]` The system program
+    AddTournamentGame,
+
+    /// #[35] Pay the prizes of a tournament and finish it
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The transactor of the final game
+    /// 1. `[writable]` The tournament account
+    /// 2. `[]` The final game account
+    /// 3. `[writable]` The tournament stake account
+    /// 4. `[]` The PDA account of the tournament
+    /// 5. `[]` The token program
+    /// 6. `[]` The system program
+    /// (Optional)7. `[]` The mint account, required for Token-2022
+    /// Rest. `[writable]` The receiver accounts, one for each prize
+    SettleTournament { params: SettleTournamentParams },
+
+    /// #[36] Set the mint of ticket tokens for a ticket game, the mint authority goes to the PDA
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The game owner, the current mint authority
+    /// 1. `[writable]` The game account
+    /// 2. `[writable]` The ticket mint account, with no decimals, supply or freeze authority
+    /// 3. `[]` The token program of the ticket mint
+    /// 4. `[]` The system program
+    SetTicketMint,
+
+    /// #[37] Buy a ticket token of a ticket game, paid with the ticket amount
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The payer account
+    /// 1. `[writable]` The temp account holding exactly the amount, or the payer's ATA (the payer
+    ///     itself for SOL) to pay directly
+    /// 2. `[writable]` The game account
+    /// 3. `[]` The mint account
+    /// 4. `[writable]` The stake account
+    /// 5. `[writable]` The ticket mint account
+    /// 6. `[writable]` The token account to receive the ticket token
+    /// 7. `[]` The PDA account
+    /// 8. `[]` The token program of the ticket mint
+    /// 9. `[]` The system program
+    /// 10. `[]` The players reg account
+    /// (Optional)11. `[]` The recipient account. For EntryType::Ticket with a fee
+    /// (Optional)12. `[writable]` The recipient slot stake account to receive the fee
+    BuyTicket,
+
+    /// #[38] Set the final table of a tournament, whose transactor pays the prizes
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The owner of the tournament
+    /// 1. `[writable]` The tournament account
+    /// 2. `[]` The game account, linked to the tournament
+    /// 3. `[]` The system program
+    SetFinalGame,
+
+    /// #[39] Close a finished tournament, the rest of the prize pool goes to the owner
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The owner of the tournament
+    /// 1. `[writable]` The tournament account
+    /// 2. `[writable]` The tournament stake account
+    /// 3. `[]` The PDA account of the tournament
+    /// 4. `[writable]` The receiver account, the owner itself for SOL or its ATA
+    /// 5. `[]` The token program
+    /// 6. `[]` The system program
+    /// (Optional)7. `[]` The mint account, required for Token-2022
+    CloseTournament,
+
+    /// #[40] Refund an expired satellite ticket to its owner
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The payer of the transaction, anyone
+    /// 1. `[writable]` The ticket account
+    /// 2. `[writable]` The game of the ticket
+    /// 3. `[writable]` The stake account, the tournament stake account for tournament games
+    /// 4. `[]` The PDA from game account, from the tournament account for tournament games
+    /// 5. `[writable]` The receiver account, the ticket owner itself for SOL or its ATA
+    /// 6. `[writable]` The rent payer of the ticket account
+    /// 7. `[]` The token program
+    /// 8. `[]` The system program
+    /// (Optional)9. `[writable]` The tournament account. For tickets into tournament games
+    /// (Optional)Last. `[]` The mint account, required for Token-2022
+    RefundTicket,
+
+    /// #[41] Burn an expired ticket token for a refund of its value
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]` The holder of the ticket token
+    /// 1. `[writable]` The token account holding the ticket token
+    /// 2. `[writable]` The game account
+    /// 3. `[writable]` The stake account
+    /// 4. `[]` The PDA account
+    /// 5. `[writable]` The receiver account, the holder itself for SOL or its ATA
+    /// 6. `[writable]` The ticket mint account
+    /// 7. `[]` The token program of the ticket mint
+    /// 8. `[]` The system program
+    /// (Optional)9. `[]` The mint account, required for Token-2022
+    RefundTicketToken,
 }
 
 impl RaceInstruction {
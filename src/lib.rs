@@ -8,3 +8,6 @@ mod state;
 mod types;
 mod instruction;
 mod constants;
+pub mod events;
+#[cfg(test)]
+mod testing;
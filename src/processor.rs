@@ -21,6 +21,29 @@ mod deposit;
 mod attach_bonus;
 mod reject_deposits;
 mod add_recipient_slot;
+mod pause_game;
+mod resume_game;
+mod cancel_game;
+mod reopen_game;
+mod update_allowlist;
+mod set_gatekeeper;
+mod sponsor_game;
+mod batch_join;
+mod migrate_players_reg;
+mod compact_players_reg;
+mod change_seat;
+mod swap_seats;
+mod kick_player;
+mod move_balance;
+mod create_tournament;
+mod add_tournament_game;
+mod settle_tournament;
+mod set_ticket_mint;
+mod buy_ticket;
+mod set_final_game;
+mod close_tournament;
+mod refund_ticket;
+mod refund_ticket_token;
 
 pub fn process(
     program_id: &Pubkey,
@@ -108,6 +131,98 @@ pub fn process(
             msg!("Add recipient slot");
             add_recipient_slot::process(program_id, accounts, params)
         }
+        RaceInstruction::PauseGame => {
+            msg!("Pause game");
+            pause_game::process(program_id, accounts)
+        }
+        RaceInstruction::ResumeGame => {
+            msg!("Resume game");
+            resume_game::process(program_id, accounts)
+        }
+        RaceInstruction::CancelGame => {
+            msg!("Cancel game");
+            cancel_game::process(program_id, accounts)
+        }
+        RaceInstruction::ReopenGame { params } => {
+            msg!("Reopen game");
+            reopen_game::process(program_id, accounts, params)
+        }
+        RaceInstruction::UpdateAllowlist { params } => {
+            msg!("Update allowlist");
+            update_allowlist::process(program_id, accounts, params)
+        }
+        RaceInstruction::SetGatekeeper { params } => {
+            msg!("Set gatekeeper");
+            set_gatekeeper::process(program_id, accounts, params)
+        }
+        RaceInstruction::SponsorGame { params } => {
+            msg!("Sponsor game");
+            sponsor_game::process(program_id, accounts, params)
+        }
+        RaceInstruction::BatchJoin { params } => {
+            msg!("Batch join");
+            batch_join::process(program_id, accounts, params)
+        }
+        RaceInstruction::MigratePlayersReg => {
+            msg!("Migrate players reg");
+            migrate_players_reg::process(program_id, accounts)
+        }
+        RaceInstruction::CompactPlayersReg => {
+            msg!("Compact players reg");
+            compact_players_reg::process(program_id, accounts)
+        }
+        RaceInstruction::ChangeSeat { params } => {
+            msg!("Change seat");
+            change_seat::process(program_id, accounts, params)
+        }
+        RaceInstruction::SwapSeats { params } => {
+            msg!("Swap seats");
+            swap_seats::process(program_id, accounts, params)
+        }
+        RaceInstruction::KickPlayer { params } => {
+            msg!("Kick player");
+            kick_player::process(program_id, accounts, params)
+        }
+        RaceInstruction::MoveBalance { params } => {
+            msg!("Move balance");
+            move_balance::process(program_id, accounts, params)
+        }
+        RaceInstruction::CreateTournament { params } => {
+            msg!("Create tournament");
+            create_tournament::process(program_id, accounts, params)
+        }
+        RaceInstruction::AddTournamentGame => {
+            msg!("Add tournament game");
+            add_tournament_game::process(program_id, accounts)
+        }
+        RaceInstruction::SettleTournament { params } => {
+            msg!("Settle tournament");
+            settle_tournament::process(program_id, accounts, params)
+        }
+        RaceInstruction::SetTicketMint => {
+            msg!("Set ticket mint");
+            set_ticket_mint::process(program_id, accounts)
+        }
+        RaceInstruction::BuyTicket => {
+            msg!("Buy ticket");
+            buy_ticket::process(program_id, accounts)
+        }
+        RaceInstruction::SetFinalGame => {
+            msg!("Set final game");
+            set_final_game::process(program_id, accounts)
+        }
+        RaceInstruction::CloseTournament => {
+            msg!("Close tournament");
+            close_tournament::process(program_id, accounts)
+        }
+        RaceInstruction::RefundTicket => {
+            msg!("Refund ticket");
+            refund_ticket::process(program_id, accounts)
+        }
+        RaceInstruction::RefundTicketToken => {
+            msg!("Refund ticket token");
+            refund_ticket_token::process(program_id, accounts)
+        }
     };
 
     if let Err(ref e) = result {
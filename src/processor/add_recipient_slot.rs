@@ -1,14 +1,13 @@
 use borsh::BorshDeserialize;
 use solana_program::{
-    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program::invoke, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey
-};
-use spl_token::{
-    instruction::{set_authority, AuthorityType},
-    state::Account,
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program::invoke, program_error::ProgramError, pubkey::Pubkey
 };
+use spl_token_2022::instruction::{set_authority, AuthorityType};
 
 use crate::{
-    error::ProcessError, processor::misc::is_native_mint,
+    error::ProcessError,
+    processor::create_game::validate_mint_extensions,
+    processor::misc::{is_native_mint, next_mint_account, unpack_token_account},
     state::{RecipientSlot, RecipientState}, types::RecipientSlotInit,
 };
 
@@ -65,13 +64,16 @@ pub fn process(
             return Err(ProcessError::InvalidSlotStakeAccount)?;
         }
     } else {
-        let stake_account_state = Account::unpack(&stake_account.try_borrow_data()?)?;
+        let stake_account_state = unpack_token_account(stake_account)?;
         if stake_account_state.mint.ne(&token_addr) {
             return Err(ProgramError::InvalidArgument);
         }
+        if let Some(mint_account) = next_mint_account(stake_account, &token_addr, accounts_iter)? {
+            validate_mint_extensions(mint_account)?;
+        }
         // Transfer the authority to PDA account
         let set_authority_ix = set_authority(
-            token_program.key,
+            stake_account.owner,
             stake_account.key,
             Some(&pda),
             AuthorityType::AccountOwner,
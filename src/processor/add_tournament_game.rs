@@ -0,0 +1,100 @@
+//! Link a game to a tournament as one of its tables.
+//!
+//! Signed by the owner of both.  The game must use the token of the tournament and have no
+//! players yet.  It takes the entry type and the schedule of the tournament, and its entries are
+//! paid to the tournament stake account from then on.
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::ProcessError,
+    events::RaceEvent,
+    state::{players, GameState, TournamentState},
+};
+
+use super::misc::pack_state_to_account;
+
+#[inline(never)]
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_iter = &mut accounts.iter();
+
+    let owner_account = next_account_info(account_iter)?;
+
+    let tournament_account = next_account_info(account_iter)?;
+
+    let game_account = next_account_info(account_iter)?;
+
+    let players_reg_account = next_account_info(account_iter)?;
+
+    let system_program = next_account_info(account_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if tournament_account.owner.ne(program_id) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let mut tournament_state = TournamentState::try_from_slice(&tournament_account.try_borrow_data()?)?;
+    if !tournament_state.is_open() {
+        return Err(ProcessError::InvalidTournament)?;
+    }
+
+    match game_account.data.borrow()[0] {
+        1 => (),
+        3 => return Err(ProcessError::GamePaused)?,
+        _ => return Err(ProgramError::UninitializedAccount),
+    }
+    let mut game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
+
+    if tournament_state.owner.ne(owner_account.key) || game_state.owner.ne(owner_account.key) {
+        return Err(ProcessError::InvalidOwner)?;
+    }
+
+    if game_state.tournament.is_some() {
+        msg!("Game is linked to {:?} already", game_state.tournament);
+        return Err(ProcessError::InvalidTournament)?;
+    }
+
+    if game_state.token_mint.ne(&tournament_state.token_mint) {
+        return Err(ProcessError::InvalidMint)?;
+    }
+
+    if players_reg_account.key.ne(&game_state.players_reg_account) {
+        return Err(ProcessError::InvalidPlayersRegAccount)?;
+    }
+
+    if players::get_players_count(&players_reg_account.try_borrow_data()?)? > 0 || !game_state.deposits.is_empty() {
+        msg!("Only games without players can be linked");
+        return Err(ProcessError::InvalidTournament)?;
+    }
+
+    game_state.tournament = Some(*tournament_account.key);
+    game_state.entry_type = tournament_state.entry_type.clone();
+    game_state.start_time = tournament_state.start_time;
+    game_state.registration_close_time = tournament_state.registration_close_time;
+    game_state.late_registration_until = tournament_state.late_registration_until;
+
+    tournament_state.games.push(*game_account.key);
+
+    pack_state_to_account(game_state, game_account, owner_account, system_program)?;
+    pack_state_to_account(tournament_state, tournament_account, owner_account, system_program)?;
+
+    msg!("Linked game {} to tournament {}", game_account.key, tournament_account.key);
+
+    RaceEvent::TournamentGameAdded {
+        tournament: *tournament_account.key,
+        game: *game_account.key,
+    }
+    .emit()?;
+
+    Ok(())
+}
@@ -2,15 +2,15 @@
 //! The bonus are stored in a dedicated token account which will be given the authority of PDA.
 //! Only SPL bonus is supported, SOL/WSOL are not supported.
 
-use crate::processor::misc::{is_native_mint, pack_state_to_account};
+use crate::processor::create_game::validate_mint_extensions;
+use crate::processor::misc::{is_native_mint, next_mint_account, pack_state_to_account, unpack_token_account};
 use crate::state::Bonus;
 use crate::types::AttachBonusParams;
 use crate::{
     error::ProcessError,
+    events::RaceEvent,
     state::GameState,
 };
-use borsh::BorshDeserialize;
-use solana_program::program_pack::Pack;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
@@ -20,8 +20,7 @@ use solana_program::{
     pubkey::Pubkey,
     sysvar::rent::Rent,
 };
-use spl_token::instruction::{set_authority, AuthorityType};
-use spl_token::state::Account;
+use spl_token_2022::instruction::{set_authority, AuthorityType};
 
 #[inline(never)]
 pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], params: AttachBonusParams) -> ProgramResult {
@@ -50,10 +49,12 @@ pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], params: AttachBonu
         return Err(ProgramError::AccountNotRentExempt);
     }
 
-    if game_account.data.borrow()[0] != 1 {
-        return Err(ProgramError::UninitializedAccount);
+    match game_account.data.borrow()[0] {
+        1 => (),
+        3 => return Err(ProcessError::GamePaused)?,
+        _ => return Err(ProgramError::UninitializedAccount),
     }
-    let mut game_state = GameState::try_from_slice(&game_account.try_borrow_data()?)?;
+    let mut game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
 
     let (pda, _bump_seed) = Pubkey::find_program_address(&[game_account.key.as_ref()], program_id);
 
@@ -63,14 +64,20 @@ pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], params: AttachBonu
 
         let temp_account = next_account_info(account_iter)?;
 
-        let temp_state = Account::unpack(&temp_account.try_borrow_data()?)?;
+        let temp_state = unpack_token_account(temp_account)?;
 
         if is_native_mint(&temp_state.mint) {
             return Err(ProcessError::NativeTokenNotSupported)?;
         }
 
+        if let Some(mint_account) = next_mint_account(temp_account, &temp_state.mint, account_iter)? {
+            validate_mint_extensions(mint_account)?;
+        }
+
         msg!("Attach bonus at {} to {}", identifier, temp_account.key);
 
+        let bonus_identifier = identifier.clone();
+
         let bonus = Bonus {
             identifier,
             amount: temp_state.amount,
@@ -81,7 +88,7 @@ pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], params: AttachBonu
         game_state.bonuses.push(bonus);
 
         let set_authority_ix = set_authority(
-            token_program.key,
+            temp_account.owner,
             temp_account.key,
             Some(&pda),
             AuthorityType::AccountOwner,
@@ -93,6 +100,14 @@ pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], params: AttachBonu
             &set_authority_ix,
             &[temp_account.clone(), payer_account.clone(), token_program.clone()],
         )?;
+
+        RaceEvent::BonusAttached {
+            game: *game_account.key,
+            identifier: bonus_identifier,
+            token: temp_state.mint,
+            amount: temp_state.amount,
+        }
+        .emit()?;
     }
 
     pack_state_to_account(game_state, game_account, payer_account, system_program)?;
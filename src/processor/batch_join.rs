@@ -0,0 +1,281 @@
+//! Seat many players at once, e.g. the qualified players of a sponsored tournament.
+//!
+//! Signed by the game owner or the transactor, who pays for all seats from a single account,
+//! Token-2022 transfer fees included.
+//! The players reg account grows once for the whole batch, and each player gets its own access
+//! version in the order given.  Allowlist, invite code and gatekeeper are not checked when the
+//! owner signs, as the owner sets them.  The transactor must pass them for every player.
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    error::ProcessError,
+    events::RaceEvent,
+    state::{players, DepositStatus, EntryType, GameState, PlayerDeposit, PlayerJoin},
+    types::BatchJoinParams,
+};
+
+use super::misc::{
+    extend_account, inverse_transfer_fee, is_join_approved, is_native_mint, next_tournament_account,
+    pack_state_to_account, token_transfer, transfer_sol, validate_fee_receiver, verify_invite_signature,
+    verify_merkle_proof,
+};
+
+#[inline(never)]
+pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], params: BatchJoinParams) -> ProgramResult {
+    let account_iter = &mut accounts.iter();
+
+    let signer_account = next_account_info(account_iter)?;
+
+    let source_account = next_account_info(account_iter)?;
+
+    let game_account = next_account_info(account_iter)?;
+
+    let players_reg_account = next_account_info(account_iter)?;
+
+    let mint_account = next_account_info(account_iter)?;
+
+    let stake_account = next_account_info(account_iter)?;
+
+    let _token_program = next_account_info(account_iter)?;
+
+    let system_program = next_account_info(account_iter)?;
+
+    if !signer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    match game_account.data.borrow()[0] {
+        1 => (),
+        3 => return Err(ProcessError::GamePaused)?,
+        _ => return Err(ProgramError::UninitializedAccount),
+    }
+    let mut game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
+
+    if game_state.owner.ne(signer_account.key)
+        && game_state.transactor_addr.ne(&Some(*signer_account.key))
+    {
+        return Err(ProcessError::InvalidOwner)?;
+    }
+
+    if game_state.settle_version < params.settle_version {
+        return Err(ProcessError::InvalidSettleVersion)?;
+    }
+
+    // The entries of tournament games go to the tournament stake account, checked below
+    if game_state.tournament.is_none() && game_state.stake_account.ne(stake_account.key) {
+        return Err(ProcessError::InvalidStakeAccount)?;
+    }
+
+    if game_state.token_mint.ne(mint_account.key) {
+        return Err(ProcessError::InvalidMint)?;
+    }
+
+    if players_reg_account.key.ne(&game_state.players_reg_account) {
+        return Err(ProcessError::InvalidPlayersRegAccount)?;
+    }
+
+    if let Some(deadline) = game_state.registration_deadline() {
+        let now = Clock::get()?.unix_timestamp as u64;
+        if now >= deadline {
+            msg!("Registration closed at {}, now: {}", deadline, now);
+            return Err(ProcessError::RegistrationClosed)?;
+        }
+    }
+
+    let players_count = params.players.len();
+    let players_len = players::get_players_count(&players_reg_account.try_borrow_data()?)?;
+    if players_len + players_count > game_state.max_players as usize {
+        return Err(ProcessError::GameFullAlready)?;
+    }
+
+    match game_state.entry_type.payment_type() {
+        EntryType::Cash { min_deposit, max_deposit, deposit_step, .. } => {
+            if params.amount < *min_deposit || params.amount > *max_deposit {
+                msg!("Invalid deposit amount: {}, min: {}, max: {}", params.amount, min_deposit, max_deposit);
+                return Err(ProcessError::InvalidPaymentParams)?;
+            }
+            if deposit_step.is_some_and(|step| !params.amount.is_multiple_of(step)) {
+                msg!("Deposit amount {} is not a multiple of {:?}", params.amount, deposit_step);
+                return Err(ProcessError::InvalidPaymentParams)?;
+            }
+        }
+        EntryType::Ticket { amount, .. } => {
+            if params.amount != *amount {
+                msg!("Invalid payment amount: {}, ticket: {}", params.amount, amount);
+                return Err(ProcessError::InvalidPaymentParams)?;
+            }
+        }
+        EntryType::Free => {
+            if params.amount != 0 {
+                msg!("Invalid payment amount: {}, freeroll", params.amount);
+                return Err(ProcessError::InvalidPaymentParams)?;
+            }
+        }
+        _ => return Err(ProcessError::InvalidEntryType)?,
+    }
+
+    // Grow the players reg account once for the slots missing
+    let free_slots = players::get_free_slots_count(&players_reg_account.try_borrow_data()?)?;
+    let new_slots = players_count.saturating_sub(free_slots);
+    if new_slots > 0 {
+        extend_account(players_reg_account, players::slots_len(new_slots), signer_account, system_program)?;
+        players::add_slots(&mut players_reg_account.try_borrow_mut_data()?, new_slots)?;
+    }
+
+    let mut positions = Vec::with_capacity(players_count);
+    for player in params.players.iter() {
+        let mut data = players_reg_account.try_borrow_mut_data()?;
+
+        if players::is_player_joined(&data, &player.addr)? {
+            msg!("Player {} joined already", player.addr);
+            return Err(ProcessError::JoinedGameAlready)?;
+        }
+
+        if player.position >= game_state.max_players {
+            return Err(ProcessError::InvalidPosition)?;
+        }
+
+        let mut position = player.position;
+        if players::is_position_occupied(&data, position)? {
+            position = players::get_available_position(&data, game_state.max_players)?;
+        }
+
+        game_state.access_version += 1;
+
+        let player_join = PlayerJoin {
+            addr: player.addr,
+            position,
+            access_version: game_state.access_version,
+        };
+        if players::add_player(&mut data, &player_join)?.is_none() {
+            return Err(ProcessError::MalformedPlayersRegAccount)?;
+        }
+        positions.push(position);
+
+        game_state.add_ticket_buyin(&player.addr, params.amount)?;
+    }
+
+    // The fee part of tickets goes to the recipient slot
+    let total_amount = params.amount.checked_mul(players_count as u64).ok_or(ProcessError::StakeAmountOverflow)?;
+    let total_fee = game_state.entry_type.ticket_fee(params.amount) * players_count as u64;
+    let fee_receiver_account = if total_amount > 0 && total_fee > 0 {
+        let recipient_account = next_account_info(account_iter)?;
+        let fee_receiver_account = next_account_info(account_iter)?;
+        validate_fee_receiver(recipient_account, &game_state.recipient_addr, &game_state.token_mint, fee_receiver_account.key)?;
+        Some(fee_receiver_account)
+    } else {
+        None
+    };
+
+    if game_state.owner.ne(signer_account.key) {
+        if let EntryType::Allowlist { merkle_root, .. } = &game_state.entry_type {
+            if let Some(player) = params.players.iter().find(|p| !verify_merkle_proof(merkle_root, &p.addr, &p.proof)) {
+                msg!("Player {} is not in allowlist", player.addr);
+                return Err(ProcessError::NotInAllowlist)?;
+            }
+        }
+
+        if let Some(gatekeeper) = &game_state.gatekeeper {
+            let approval_account = next_account_info(account_iter)?;
+            for player in params.players.iter() {
+                if !is_join_approved(approval_account, gatekeeper, game_account.key, &player.addr)? {
+                    msg!("Player {} is not approved", player.addr);
+                    return Err(ProcessError::JoinNotApproved)?;
+                }
+            }
+        }
+
+        if let Some(invite_key) = &game_state.invite_key {
+            let instructions_sysvar = next_account_info(account_iter)?;
+            for player in params.players.iter() {
+                if !verify_invite_signature(instructions_sysvar, invite_key, game_account.key, &player.addr)? {
+                    msg!("Player {} is not invited", player.addr);
+                    return Err(ProcessError::InvalidInviteCode)?;
+                }
+            }
+        }
+    }
+
+    let mut tournament = next_tournament_account(&game_state, account_iter)?;
+    if let Some((_, tournament_state)) = &mut tournament {
+        if tournament_state.stake_account.ne(stake_account.key) {
+            return Err(ProcessError::InvalidStakeAccount)?;
+        }
+        for player in params.players.iter() {
+            tournament_state.add_player(&player.addr, game_account.key)?;
+        }
+    }
+
+    // Pay for all players in one transfer.  The signer pays the transfer fees of Token-2022 on
+    // top, so every player is credited the full amount less the ticket fee.
+    let stake_amount = total_amount - total_fee;
+    let received = if total_amount == 0 {
+        0
+    } else if is_native_mint(mint_account.key) {
+        if let Some(fee_receiver_account) = fee_receiver_account {
+            transfer_sol(source_account.clone(), fee_receiver_account.clone(), Some(total_fee), &[])?;
+        }
+        transfer_sol(source_account.clone(), stake_account.clone(), Some(stake_amount), &[])?;
+        stake_amount
+    } else {
+        if let Some(fee_receiver_account) = fee_receiver_account {
+            token_transfer(source_account, fee_receiver_account, signer_account, Some(mint_account), total_fee, &[])?;
+        }
+        let transfer_amount = stake_amount
+            .checked_add(inverse_transfer_fee(mint_account, stake_amount)?)
+            .ok_or(ProcessError::StakeAmountOverflow)?;
+        token_transfer(source_account, stake_account, signer_account, Some(mint_account), transfer_amount, &[])?
+    };
+
+    // The rounding of the transfer fee may leave a little more, kept as sponsor funds, or in the
+    // prize pool for tournament games
+    let amount_per_player = params.amount - game_state.entry_type.ticket_fee(params.amount);
+    let excess = received.checked_sub(stake_amount).ok_or(ProcessError::InvalidDeposit)?;
+    if tournament.is_none() {
+        game_state.sponsor_funds += excess;
+    }
+
+    let first_access_version = game_state.access_version - players_count as u64;
+    for (i, player) in params.players.iter().enumerate() {
+        game_state.deposits.push(PlayerDeposit {
+            addr: player.addr,
+            amount: amount_per_player,
+            access_version: first_access_version + i as u64 + 1,
+            settle_version: params.settle_version,
+            status: DepositStatus::Pending,
+        });
+    }
+
+    players::set_versions(&mut players_reg_account.try_borrow_mut_data()?, game_state.access_version, game_state.settle_version)?;
+
+    pack_state_to_account(game_state, game_account, signer_account, system_program)?;
+
+    if let Some((tournament_account, mut tournament_state)) = tournament {
+        tournament_state.prize_pool += received;
+        pack_state_to_account(tournament_state, tournament_account, signer_account, system_program)?;
+    }
+
+    for (i, (player, position)) in params.players.iter().zip(positions).enumerate() {
+        RaceEvent::PlayerJoined {
+            game: *game_account.key,
+            player: player.addr,
+            position,
+            access_version: first_access_version + i as u64 + 1,
+            amount: amount_per_player,
+        }
+        .emit()?;
+    }
+
+    msg!("{} players joined game", players_count);
+
+    Ok(())
+}
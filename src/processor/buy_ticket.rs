@@ -0,0 +1,237 @@
+//! Buy a ticket token of a ticket game.
+//!
+//! The ticket amount is paid to the stake account like a join, the fee part included, and one
+//! ticket token is minted to the buyer.  Ticket tokens can be traded, and are burned when joining
+//! the game in place of the payment.  Every token in circulation holds a seat, so no more tokens
+//! are sold than the seats left.  Once the game can't be joined anymore, tokens are burned for a
+//! refund of their value with `RefundTicketToken`, the fee part is not paid back.
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke_signed,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_token_2022::instruction::mint_to;
+
+use crate::{
+    error::ProcessError,
+    events::RaceEvent,
+    state::{players, EntryType, GameState},
+};
+
+use super::misc::{collect_payment, is_token_program, pack_state_to_account, unpack_mint, validate_fee_receiver};
+
+#[inline(never)]
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_iter = &mut accounts.iter();
+
+    let payer_account = next_account_info(account_iter)?;
+
+    let source_account = next_account_info(account_iter)?;
+
+    let game_account = next_account_info(account_iter)?;
+
+    let mint_account = next_account_info(account_iter)?;
+
+    let stake_account = next_account_info(account_iter)?;
+
+    let ticket_mint_account = next_account_info(account_iter)?;
+
+    let receiver_account = next_account_info(account_iter)?;
+
+    let pda_account = next_account_info(account_iter)?;
+
+    let token_program = next_account_info(account_iter)?;
+
+    let system_program = next_account_info(account_iter)?;
+
+    let players_reg_account = next_account_info(account_iter)?;
+
+    if !payer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    match game_account.data.borrow()[0] {
+        1 => (),
+        3 => return Err(ProcessError::GamePaused)?,
+        _ => return Err(ProgramError::UninitializedAccount),
+    }
+    let mut game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
+
+    if game_state.stake_account.ne(stake_account.key) {
+        return Err(ProcessError::InvalidStakeAccount)?;
+    }
+
+    if game_state.token_mint.ne(mint_account.key) {
+        return Err(ProcessError::InvalidMint)?;
+    }
+
+    if game_state.ticket_mint.ne(&Some(*ticket_mint_account.key))
+        || !is_token_program(token_program.key)
+        || ticket_mint_account.owner.ne(token_program.key)
+    {
+        return Err(ProcessError::InvalidTicket)?;
+    }
+
+    if players_reg_account.key.ne(&game_state.players_reg_account) {
+        return Err(ProcessError::InvalidPlayersRegAccount)?;
+    }
+
+    // Seats are held by the joined players and by the ticket tokens in circulation
+    let players_count = players::get_players_count(&players_reg_account.try_borrow_data()?)? as u64;
+    let supply = unpack_mint(ticket_mint_account)?.supply;
+    if players_count + supply >= game_state.max_players as u64 {
+        msg!("{} players joined and {} ticket tokens in circulation", players_count, supply);
+        return Err(ProcessError::GameFullAlready)?;
+    }
+
+    let (pda, bump_seed) = Pubkey::find_program_address(&[game_account.key.as_ref()], program_id);
+
+    if pda.ne(pda_account.key) {
+        return Err(ProcessError::InvalidPDA)?;
+    }
+
+    if let Some(deadline) = game_state.registration_deadline() {
+        let now = Clock::get()?.unix_timestamp as u64;
+        if now >= deadline {
+            msg!("Registration closed at {}, now: {}", deadline, now);
+            return Err(ProcessError::RegistrationClosed)?;
+        }
+    }
+
+    let EntryType::Ticket { amount, .. } = game_state.entry_type.payment_type() else {
+        return Err(ProcessError::InvalidEntryType)?;
+    };
+    let amount = *amount;
+
+    // The fee part of a ticket is routed to the recipient slot at once
+    let fee = game_state.entry_type.ticket_fee(amount);
+    let fee_receiver = if fee > 0 {
+        let recipient_account = next_account_info(account_iter)?;
+        let fee_receiver_account = next_account_info(account_iter)?;
+        validate_fee_receiver(
+            recipient_account,
+            &game_state.recipient_addr,
+            &game_state.token_mint,
+            fee_receiver_account.key,
+        )?;
+        Some((fee_receiver_account, fee))
+    } else {
+        None
+    };
+
+    let received = collect_payment(
+        payer_account,
+        source_account,
+        stake_account,
+        fee_receiver,
+        mint_account,
+        amount,
+    )?;
+
+    game_state.presell_ticket(received)?;
+
+    let mint_to_ix = mint_to(
+        token_program.key,
+        ticket_mint_account.key,
+        receiver_account.key,
+        pda_account.key,
+        &[],
+        1,
+    )?;
+
+    invoke_signed(
+        &mint_to_ix,
+        &[ticket_mint_account.clone(), receiver_account.clone(), pda_account.clone(), token_program.clone()],
+        &[&[game_account.key.as_ref(), &[bump_seed]]],
+    )?;
+
+    pack_state_to_account(game_state, game_account, payer_account, system_program)?;
+
+    msg!("Ticket of game {} bought by {}", game_account.key, payer_account.key);
+
+    RaceEvent::TicketBought {
+        game: *game_account.key,
+        buyer: *payer_account.key,
+        amount: received,
+    }
+    .emit()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::ProcessError,
+        instruction::RaceInstruction,
+        state::PlayerJoin,
+        testing::{players_reg_data, process_instruction, ticket_game, TestAccount},
+    };
+    use solana_program::{program_option::COption, program_pack::Pack, pubkey::Pubkey};
+    use spl_token::state::Mint;
+
+    #[test]
+    fn test_buy_ticket_beyond_seats() {
+        let program_id = Pubkey::new_unique();
+        let game_key = Pubkey::new_unique();
+        let (pda, _) = Pubkey::find_program_address(&[game_key.as_ref()], &program_id);
+
+        // One seat taken by a player, and one by a ticket token
+        let mut ticket_mint_data = vec![0; Mint::LEN];
+        Mint::pack(
+            Mint {
+                mint_authority: COption::Some(pda),
+                supply: 1,
+                decimals: 0,
+                is_initialized: true,
+                freeze_authority: COption::None,
+            },
+            &mut ticket_mint_data,
+        )
+        .unwrap();
+        let mut ticket_mint = TestAccount::new(&spl_token::id(), 1, ticket_mint_data);
+        let players = [PlayerJoin { addr: Pubkey::new_unique(), position: 0, access_version: 1 }];
+        let mut reg = TestAccount::program_account(&program_id, players_reg_data(2, &players));
+
+        let mut stake = TestAccount::wallet(0).with_key(pda);
+        let mut game_state = ticket_game(100, None, None);
+        game_state.max_players = 2;
+        game_state.stake_account = pda;
+        game_state.players_reg_account = reg.key;
+        game_state.ticket_mint = Some(ticket_mint.key);
+        let mut game = TestAccount::program_account(&program_id, borsh::to_vec(&game_state).unwrap()).with_key(game_key);
+
+        let mut payer = TestAccount::wallet(1_000).signer();
+        let mut source = payer.clone();
+        let mut mint = TestAccount::wallet(0).with_key(spl_token::native_mint::id());
+        let mut receiver = TestAccount::wallet(0);
+        let mut pda_account = stake.clone();
+        let mut token_program = TestAccount::wallet(0).with_key(spl_token::id());
+        let mut system_program = TestAccount::system_program();
+
+        let result = process_instruction(
+            &program_id,
+            &mut [
+                &mut payer,
+                &mut source,
+                &mut game,
+                &mut mint,
+                &mut stake,
+                &mut ticket_mint,
+                &mut receiver,
+                &mut pda_account,
+                &mut token_program,
+                &mut system_program,
+                &mut reg,
+            ],
+            RaceInstruction::BuyTicket,
+        );
+        assert_eq!(result, Err(ProcessError::GameFullAlready.into()));
+    }
+}
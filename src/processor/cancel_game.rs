@@ -0,0 +1,480 @@
+//! Cancel a ticket game that never started.
+//!
+//! Available to the game owner and the transactor before the first settlement of the session, a
+//! reopened game can be cancelled again.  Every unhandled deposit is refunded to its owner, all
+//! players are removed and the game is marked as cancelled.  The ticket fee taken by the recipient
+//! is paid back too, unless the recipient shares have claimed it already.
+//!
+//! The deposits of tournament games are refunded from the tournament stake account and taken out
+//! of the prize pool, and the players of the game leave the tournament.
+//!
+//! A deposit whose receiver is invalid is left rejected.  Cancelling again refunds the rest, and
+//! the game can't be closed or reopened until all deposits are refunded.  Satellite tickets into
+//! the game expire with the cancellation, their owners get them refunded with `RefundTicket`.
+//!
+//! So do the ticket tokens in circulation, their holders get them refunded with
+//! `RefundTicketToken`.  A tournament game where players hold the balances moved from other
+//! tables can't be cancelled.
+
+use crate::state::{players, DepositStatus, EntryType, GameStatus, RecipientSlot, RecipientState};
+use crate::{error::ProcessError, events::RaceEvent, state::GameState};
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+};
+
+use super::misc::{
+    general_transfer, is_native_mint, next_mint_account, next_tournament_account, pack_state_to_account,
+    unpack_token_account, validate_receiver,
+};
+
+/// The recipient slot which holds the ticket fees of the game, to pay them back.
+struct FeeRefund<'a, 'b> {
+    slot_stake_account: &'a AccountInfo<'b>,
+    slot_pda_account: &'a AccountInfo<'b>,
+    recipient_addr: Pubkey,
+    slot: RecipientSlot,
+    bump_seed: u8,
+    available: u64,
+}
+
+/// Take the recipient account, the PDA of its slot and the slot stake account from
+/// `account_iter` when the ticket of the game has a fee.
+#[inline(never)]
+fn next_fee_refund<'a, 'b, I: Iterator<Item = &'a AccountInfo<'b>>>(
+    program_id: &Pubkey,
+    game_state: &GameState,
+    account_iter: &mut I,
+) -> Result<Option<FeeRefund<'a, 'b>>, ProgramError> {
+    if !matches!(game_state.entry_type.payment_type(), EntryType::Ticket { fee: Some(_), .. }) {
+        return Ok(None);
+    }
+
+    let recipient_account = next_account_info(account_iter)?;
+    let slot_pda_account = next_account_info(account_iter)?;
+    let slot_stake_account = next_account_info(account_iter)?;
+
+    if recipient_account.key.ne(&game_state.recipient_addr) {
+        return Err(ProcessError::InvalidRecipientAddress)?;
+    }
+    let recipient_state = RecipientState::try_from_slice(&recipient_account.try_borrow_data()?)?;
+    let Some(slot) = recipient_state.slots.into_iter().find(|s| s.token_addr.eq(&game_state.token_mint)) else {
+        return Err(ProcessError::RecipientSlotNotFound)?;
+    };
+    if slot.stake_addr.ne(slot_stake_account.key) {
+        return Err(ProcessError::InvalidSlotStakeAccount)?;
+    }
+
+    let (pda, bump_seed) = Pubkey::find_program_address(&[recipient_account.key.as_ref(), &[slot.id]], program_id);
+    if pda.ne(slot_pda_account.key) {
+        return Err(ProcessError::InvalidPDA)?;
+    }
+
+    let available = if is_native_mint(&game_state.token_mint) {
+        slot_stake_account.lamports()
+    } else {
+        unpack_token_account(slot_stake_account)?.amount
+    };
+
+    Ok(Some(FeeRefund {
+        slot_stake_account,
+        slot_pda_account,
+        recipient_addr: *recipient_account.key,
+        slot,
+        bump_seed,
+        available,
+    }))
+}
+
+#[inline(never)]
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let mut account_iter = accounts.iter();
+
+    let signer_account = next_account_info(&mut account_iter)?;
+
+    let game_account = next_account_info(&mut account_iter)?;
+
+    let players_reg_account = next_account_info(&mut account_iter)?;
+
+    let stake_account = next_account_info(&mut account_iter)?;
+
+    let pda_account = next_account_info(&mut account_iter)?;
+
+    let _token_program = next_account_info(&mut account_iter)?;
+
+    let system_program = next_account_info(&mut account_iter)?;
+
+    let owner_account = next_account_info(&mut account_iter)?;
+
+    if !signer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Cancelled games can be cancelled again to refund the deposits left
+    if !matches!(game_account.data.borrow()[0], 1 | 3 | 4) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let mut game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
+
+    if game_state.owner.ne(signer_account.key)
+        && game_state.transactor_addr.ne(&Some(*signer_account.key))
+    {
+        return Err(ProcessError::InvalidOwner)?;
+    }
+
+    if game_state.owner.ne(owner_account.key) {
+        return Err(ProcessError::InvalidOwner)?;
+    }
+
+    if players_reg_account.key.ne(&game_state.players_reg_account) {
+        return Err(ProcessError::InvalidPlayersRegAccount)?;
+    }
+
+    if !matches!(game_state.entry_type.payment_type(), EntryType::Ticket { .. }) || game_state.is_started() {
+        return Err(ProcessError::CantCancelGame)?;
+    }
+
+    // Balances are only credited by settlements, or by moving players between tournament tables
+    if game_state.balances.iter().any(|b| b.balance > 0) {
+        msg!("Players hold balances in the game");
+        return Err(ProcessError::CantCancelGame)?;
+    }
+
+    let mint_account = next_mint_account(stake_account, &game_state.token_mint, &mut account_iter)?;
+
+    let mut fee_refund = next_fee_refund(program_id, &game_state, &mut account_iter)?;
+
+    // The deposits of tournament games are refunded from the tournament stake account
+    let mut tournament = next_tournament_account(&game_state, &mut account_iter)?;
+    let (stake_owner, expected_stake) = match &tournament {
+        Some((tournament_account, tournament_state)) => (*tournament_account.key, tournament_state.stake_account),
+        None => (*game_account.key, game_state.stake_account),
+    };
+    if expected_stake.ne(stake_account.key) {
+        return Err(ProcessError::InvalidStakeAccount)?;
+    }
+
+    let (pda, bump_seed) = Pubkey::find_program_address(&[stake_owner.as_ref()], program_id);
+    if pda.ne(pda_account.key) {
+        return Err(ProcessError::InvalidPDA)?;
+    }
+
+    // Refund every deposit which is still held in the stake account, receivers must be given in
+    // the same order as the deposits.
+    for deposit in game_state
+        .deposits
+        .iter_mut()
+        .filter(|d| matches!(d.status, DepositStatus::Pending | DepositStatus::Rejected))
+    {
+        // The deposit leaves the prize pool once, even if the refund is left for later
+        if let Some((_, tournament_state)) = tournament.as_mut().filter(|_| deposit.status == DepositStatus::Pending) {
+            tournament_state.refund(deposit.amount)?;
+        }
+        deposit.status = DepositStatus::Rejected;
+
+        let receiver_account = next_account_info(&mut account_iter)?;
+
+        if validate_receiver(&deposit.addr, &game_state.token_mint, receiver_account.key).is_err() {
+            msg!("Skip the refund of deposit {}", deposit.access_version);
+            continue;
+        }
+
+        general_transfer(
+            stake_account,
+            receiver_account,
+            &game_state.token_mint,
+            Some(deposit.amount),
+            pda_account,
+            &[&[stake_owner.as_ref(), &[bump_seed]]],
+            mint_account,
+        )?;
+
+        // The fee is paid back only when it leaves every recipient share its claimed part
+        let fee = game_state.entry_type.deposit_fee(deposit.amount);
+        if let Some(fee_refund) = fee_refund.as_mut().filter(|_| fee > 0) {
+            if fee_refund.slot.can_pay_back(fee_refund.available, fee) {
+                general_transfer(
+                    fee_refund.slot_stake_account,
+                    receiver_account,
+                    &game_state.token_mint,
+                    Some(fee),
+                    fee_refund.slot_pda_account,
+                    &[&[fee_refund.recipient_addr.as_ref(), &[fee_refund.slot.id], &[fee_refund.bump_seed]]],
+                    mint_account,
+                )?;
+                fee_refund.available -= fee;
+            } else {
+                msg!("Recipient slot keeps the fee of deposit {}", deposit.access_version);
+            }
+        }
+
+        deposit.status = DepositStatus::Refunded;
+
+        RaceEvent::Rejected {
+            game: *game_account.key,
+            player: deposit.addr,
+            access_version: deposit.access_version,
+            amount: deposit.amount,
+        }
+        .emit()?;
+    }
+
+    game_state.deposits.retain(|d| d.status == DepositStatus::Rejected);
+    game_state.buyins.clear();
+    game_state.game_status = GameStatus::Cancelled;
+    game_state.access_version += 1;
+
+    // Keep the layout of the players reg, a legacy one is only cleared
+    let layout_version = players::get_layout_version(&players_reg_account.try_borrow_data()?)?;
+    if layout_version == players::LAYOUT_VERSION {
+        players_reg_account.realloc(players::head_len(game_state.max_players), false)?;
+        players::init_account_data(&mut players_reg_account.try_borrow_mut_data()?, game_state.max_players)?;
+    } else {
+        players::clear_players(&mut players_reg_account.try_borrow_mut_data()?)?;
+        players_reg_account.realloc(players::HEAD_LEN, true)?;
+    }
+    players::set_versions(&mut players_reg_account.try_borrow_mut_data()?, game_state.access_version, game_state.settle_version)?;
+
+    // The rent of the dropped slots goes back to the owner
+    let minimum_rent = Rent::get()?.minimum_balance(players_reg_account.data_len());
+    let refund = players_reg_account.lamports().saturating_sub(minimum_rent);
+    **owner_account.lamports.borrow_mut() = owner_account
+        .lamports()
+        .checked_add(refund)
+        .ok_or(ProcessError::StakeAmountOverflow)?;
+    **players_reg_account.lamports.borrow_mut() -= refund;
+
+    pack_state_to_account(game_state, game_account, signer_account, system_program)?;
+
+    if let Some((tournament_account, mut tournament_state)) = tournament {
+        tournament_state.players.retain(|p| p.game_addr.ne(game_account.key));
+        pack_state_to_account(tournament_state, tournament_account, signer_account, system_program)?;
+    }
+
+    RaceEvent::GameCancelled { game: *game_account.key }.emit()?;
+
+    msg!("Cancelled game {}", game_account.key);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::ProcessError,
+        instruction::RaceInstruction,
+        state::{
+            players, DepositStatus, GameState, GameStatus, PlayerBalance, PlayerDeposit, PlayerJoin, RecipientSlot,
+            RecipientSlotOwner, RecipientSlotShare, RecipientSlotType, RecipientState,
+        },
+        testing::{cash_game, players_reg_data, process_instruction, ticket_game, TestAccount},
+    };
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+
+    struct Cancel {
+        owner: TestAccount,
+        game: TestAccount,
+        players_reg: TestAccount,
+        stake: TestAccount,
+        token_program: TestAccount,
+        system_program: TestAccount,
+        recipient: TestAccount,
+        slot_stake: TestAccount,
+        alice: TestAccount,
+        bob: TestAccount,
+        game_state: GameState,
+    }
+
+    impl Cancel {
+        /// A ticket game of 100 with a fee of 10, where `alice` and `bob` deposited.  The slot
+        /// holds both fees, with `claim_amounts` claimed by its two shares.
+        fn new(program_id: &Pubkey, claim_amounts: [u64; 2]) -> Self {
+            let owner = TestAccount::wallet(0).signer();
+            let alice = TestAccount::wallet(0);
+            let bob = TestAccount::wallet(0);
+            let game_key = Pubkey::new_unique();
+            let (pda, _) = Pubkey::find_program_address(&[game_key.as_ref()], program_id);
+
+            let recipient_key = Pubkey::new_unique();
+            let (slot_pda, _) = Pubkey::find_program_address(&[recipient_key.as_ref(), &[0]], program_id);
+            let recipient_state = RecipientState {
+                is_initialized: true,
+                cap_addr: None,
+                slots: vec![RecipientSlot {
+                    id: 0,
+                    slot_type: RecipientSlotType::Token,
+                    token_addr: spl_token::native_mint::id(),
+                    stake_addr: slot_pda,
+                    shares: claim_amounts
+                        .iter()
+                        .map(|claim_amount| RecipientSlotShare {
+                            owner: RecipientSlotOwner::Assigned { addr: Pubkey::new_unique() },
+                            weights: 1,
+                            claim_amount: *claim_amount,
+                        })
+                        .collect(),
+                }],
+            };
+            let recipient = TestAccount::program_account(program_id, borsh::to_vec(&recipient_state).unwrap())
+                .with_key(recipient_key);
+            let slot_stake = TestAccount::wallet(20 - claim_amounts.iter().sum::<u64>()).with_key(slot_pda);
+
+            let players = [
+                PlayerJoin { addr: alice.key, position: 0, access_version: 1 },
+                PlayerJoin { addr: bob.key, position: 1, access_version: 2 },
+            ];
+            let players_reg = TestAccount::program_account(program_id, players_reg_data(6, &players));
+
+            let mut game_state = ticket_game(100, None, Some(10));
+            game_state.owner = owner.key;
+            game_state.players_reg_account = players_reg.key;
+            game_state.stake_account = pda;
+            game_state.recipient_addr = recipient_key;
+            game_state.access_version = 2;
+            game_state.deposits = [&alice, &bob]
+                .iter()
+                .enumerate()
+                .map(|(i, player)| PlayerDeposit {
+                    addr: player.key,
+                    amount: 90,
+                    access_version: i as u64 + 1,
+                    settle_version: 0,
+                    status: DepositStatus::Pending,
+                })
+                .collect();
+            let game = TestAccount::program_account(program_id, borsh::to_vec(&game_state).unwrap()).with_key(game_key);
+
+            Cancel {
+                owner,
+                game,
+                players_reg,
+                stake: TestAccount::wallet(180).with_key(pda),
+                token_program: TestAccount::wallet(0),
+                system_program: TestAccount::system_program(),
+                recipient,
+                slot_stake,
+                alice,
+                bob,
+                game_state,
+            }
+        }
+
+        fn set_game_state(&mut self, game_state: &GameState) {
+            self.game.data = borsh::to_vec(game_state).unwrap();
+        }
+
+        fn process(&mut self, program_id: &Pubkey) -> ProgramResult {
+            let mut pda = self.stake.clone();
+            let mut owner = self.owner.clone();
+            let mut slot_pda = self.slot_stake.clone();
+            process_instruction(
+                program_id,
+                &mut [
+                    &mut self.owner,
+                    &mut self.game,
+                    &mut self.players_reg,
+                    &mut self.stake,
+                    &mut pda,
+                    &mut self.token_program,
+                    &mut self.system_program,
+                    &mut owner,
+                    &mut self.recipient,
+                    &mut slot_pda,
+                    &mut self.slot_stake,
+                    &mut self.alice,
+                    &mut self.bob,
+                ],
+                RaceInstruction::CancelGame,
+            )
+        }
+    }
+
+    #[test]
+    fn test_cancel_game_refunds_deposits_and_fees() {
+        let program_id = Pubkey::new_unique();
+        let mut cancel = Cancel::new(&program_id, [0, 0]);
+        let reg_lamports = cancel.players_reg.lamports;
+
+        cancel.process(&program_id).unwrap();
+
+        assert_eq!(cancel.alice.lamports, 100);
+        assert_eq!(cancel.bob.lamports, 100);
+        assert_eq!(cancel.stake.lamports, 0);
+        assert_eq!(cancel.slot_stake.lamports, 0);
+
+        let game_state = cancel.game.game_state();
+        assert_eq!(game_state.game_status, GameStatus::Cancelled);
+        assert_eq!(game_state.access_version, 3);
+        assert!(game_state.deposits.is_empty());
+        assert_eq!(players::get_players_count(&cancel.players_reg.data).unwrap(), 0);
+        assert_eq!(players::get_slots_count(&cancel.players_reg.data).unwrap(), 0);
+
+        // The rent of the removed slots goes to the owner
+        assert!(cancel.owner.lamports > 0);
+        assert_eq!(cancel.owner.lamports + cancel.players_reg.lamports, reg_lamports);
+    }
+
+    #[test]
+    fn test_cancel_game_keeps_claimed_fees() {
+        let program_id = Pubkey::new_unique();
+        // The first share claimed its half of both fees, paying any back would exceed its part
+        let mut cancel = Cancel::new(&program_id, [10, 0]);
+
+        cancel.process(&program_id).unwrap();
+
+        assert_eq!(cancel.alice.lamports, 90);
+        assert_eq!(cancel.bob.lamports, 90);
+        assert_eq!(cancel.stake.lamports, 0);
+        assert_eq!(cancel.slot_stake.lamports, 10);
+        assert_eq!(cancel.game.game_state().game_status, GameStatus::Cancelled);
+    }
+
+    #[test]
+    fn test_cancel_game_failures() {
+        let program_id = Pubkey::new_unique();
+        let mut cancel = Cancel::new(&program_id, [0, 0]);
+        let game_state = cancel.game_state.clone();
+
+        // Only ticket games can be cancelled
+        cancel.set_game_state(&GameState { entry_type: cash_game(10, 100).entry_type, ..game_state.clone() });
+        assert_eq!(cancel.process(&program_id), Err(ProcessError::CantCancelGame.into()));
+
+        // Not once the game is settled
+        cancel.set_game_state(&GameState { settle_version: 1, ..game_state.clone() });
+        assert_eq!(cancel.process(&program_id), Err(ProcessError::CantCancelGame.into()));
+
+        // Nor when players hold balances
+        let balances = vec![PlayerBalance { player_id: 1, balance: 100 }];
+        cancel.set_game_state(&GameState { balances, ..game_state.clone() });
+        assert_eq!(cancel.process(&program_id), Err(ProcessError::CantCancelGame.into()));
+
+        // Neither by others than the owner and the transactor
+        cancel.set_game_state(&GameState { owner: Pubkey::new_unique(), ..game_state.clone() });
+        assert_eq!(cancel.process(&program_id), Err(ProcessError::InvalidOwner.into()));
+
+        // Nothing is refunded by a failed cancellation
+        assert_eq!(cancel.stake.lamports, 180);
+        assert_eq!(cancel.alice.lamports, 0);
+        assert_eq!(cancel.game.game_state().game_status, GameStatus::Initialized);
+    }
+
+    #[test]
+    fn test_cancel_reopened_game() {
+        let program_id = Pubkey::new_unique();
+        let mut cancel = Cancel::new(&program_id, [0, 0]);
+
+        // A reopened game starts with the settle version of the previous session
+        let game_state = GameState { settle_version: 5, start_settle_version: 5, ..cancel.game_state.clone() };
+        cancel.set_game_state(&game_state);
+        cancel.process(&program_id).unwrap();
+
+        let game_state = cancel.game.game_state();
+        assert_eq!(game_state.game_status, GameStatus::Cancelled);
+        assert_eq!(players::get_players_count(&cancel.players_reg.data).unwrap(), 0);
+    }
+}
@@ -0,0 +1,86 @@
+//! Move a player to another seat.
+//!
+//! Signed by the player, only while the entry lock allows joining.  The access version is bumped,
+//! so the transactor picks up the new position the same way it picks up new joins.
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::ProcessError,
+    events::RaceEvent,
+    state::{players, EntryLock, GameState},
+    types::ChangeSeatParams,
+};
+
+use super::misc::pack_state_to_account;
+
+#[inline(never)]
+pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], params: ChangeSeatParams) -> ProgramResult {
+    let account_iter = &mut accounts.iter();
+
+    let player_account = next_account_info(account_iter)?;
+
+    let game_account = next_account_info(account_iter)?;
+
+    let players_reg_account = next_account_info(account_iter)?;
+
+    let system_program = next_account_info(account_iter)?;
+
+    if !player_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    match game_account.data.borrow()[0] {
+        1 => (),
+        3 => return Err(ProcessError::GamePaused)?,
+        _ => return Err(ProgramError::UninitializedAccount),
+    }
+    let mut game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
+
+    if players_reg_account.key.ne(&game_state.players_reg_account) {
+        return Err(ProcessError::InvalidPlayersRegAccount)?;
+    }
+
+    if !matches!(game_state.entry_lock, EntryLock::Open | EntryLock::JoinOnly) {
+        return Err(ProcessError::EntryLocked)?;
+    }
+
+    if params.position >= game_state.max_players {
+        return Err(ProcessError::InvalidPosition)?;
+    }
+
+    let mut data = players_reg_account.try_borrow_mut_data()?;
+
+    let (index, _) = players::get_player_by_addr(&data, player_account.key)?
+        .ok_or(ProcessError::PlayerNotInGame)?;
+
+    players::change_position(&mut data, index, params.position)?;
+
+    game_state.access_version += 1;
+
+    players::set_versions(&mut data, game_state.access_version, game_state.settle_version)?;
+
+    drop(data);
+
+    let access_version = game_state.access_version;
+
+    pack_state_to_account(game_state, game_account, player_account, system_program)?;
+
+    msg!("Player {} changed seat to {}", player_account.key, params.position);
+
+    RaceEvent::SeatChanged {
+        game: *game_account.key,
+        player: *player_account.key,
+        position: params.position,
+        access_version,
+    }
+    .emit()?;
+
+    Ok(())
+}
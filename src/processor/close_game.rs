@@ -1,9 +1,7 @@
-use borsh::BorshDeserialize;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
     msg,
-    program::invoke_signed,
     program_error::ProgramError,
     pubkey::Pubkey,
     sysvar::{rent::Rent, Sysvar},
@@ -11,10 +9,10 @@ use solana_program::{
 
 use crate::{
     error::ProcessError,
-    processor::misc::{general_transfer, is_native_mint},
-    state::GameState,
+    events::RaceEvent,
+    processor::misc::{close_token_account, general_transfer, is_native_mint, next_mint_account, release_ticket_tokens},
+    state::{ClosedGameState, GameState, GameStatus},
 };
-use spl_token::instruction::close_account;
 
 use super::misc::validate_receiver;
 
@@ -25,12 +23,12 @@ fn claim_bonuses<'a, 'b, 'c, I: Iterator<Item = &'a AccountInfo<'b>>>(
     game_account: &'a AccountInfo<'b>,
     pda_account: &'a AccountInfo<'b>,
     bump_seed: u8,
-    token_program: &'a AccountInfo<'b>,
     account_iter: &'c mut I,
 ) -> ProgramResult {
     for bonus in game_state.bonuses.iter() {
         let bonus_account = next_account_info(account_iter)?;
         let receiver_account = next_account_info(account_iter)?;
+        let bonus_mint_account = next_mint_account(bonus_account, &bonus.token_addr, account_iter)?;
 
         if bonus.stake_addr.ne(&bonus_account.key) {
             return Err(ProcessError::InvalidStakeAccount)?;
@@ -50,24 +48,13 @@ fn claim_bonuses<'a, 'b, 'c, I: Iterator<Item = &'a AccountInfo<'b>>>(
             None,
             pda_account,
             &[&[game_account.key.as_ref(), &[bump_seed]]],
-            token_program,
+            bonus_mint_account,
         )?;
 
-        let close_ix = close_account(
-            token_program.key,
-            bonus_account.key,
-            owner_account.key,
-            pda_account.key,
-            &[pda_account.key],
-        )?;
-
-        invoke_signed(
-            &close_ix,
-            &[
-                bonus_account.clone(),
-                owner_account.clone(),
-                pda_account.clone(),
-            ],
+        close_token_account(
+            bonus_account,
+            owner_account,
+            pda_account,
             &[&[game_account.key.as_ref(), &[bump_seed]]],
         )?;
     }
@@ -86,17 +73,30 @@ pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     let stake_account = next_account_info(account_iter)?;
     let pda_account = next_account_info(account_iter)?;
     let receiver_account = next_account_info(account_iter)?;
-    let token_program = next_account_info(account_iter)?;
+    let _token_program = next_account_info(account_iter)?;
     let _system_program = next_account_info(account_iter)?;
 
-    if game_account.data.borrow()[0] != 1 {
+    // Paused and cancelled games can be closed as well
+    if !matches!(game_account.data.borrow()[0], 1 | 3 | 4) {
         return Err(ProgramError::UninitializedAccount);
     }
-    let game_state = GameState::try_from_slice(&game_account.try_borrow_data()?)?;
+    let mut game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
 
     if game_state.owner.ne(&owner_account.key) {
         return Err(ProcessError::InvalidOwner)?;
     }
+
+    // The deposits left by a cancellation still belong to the players
+    if game_state.game_status == GameStatus::Cancelled && !game_state.deposits.is_empty() {
+        msg!("Refund the deposits left by cancelling the game again before closing");
+        return Err(ProcessError::OutstandingFunds)?;
+    }
+
+    // So are the funds of satellite tickets not redeemed yet, pausing the game lets them be refunded
+    if game_state.ticket_funds > 0 {
+        msg!("Tickets worth {} are not redeemed or refunded yet", game_state.ticket_funds);
+        return Err(ProcessError::OutstandingFunds)?;
+    }
     if game_state.stake_account.ne(stake_account.key) {
         return Err(ProcessError::InvalidStakeAccount)?;
     }
@@ -109,6 +109,11 @@ pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
         return Err(ProcessError::InvalidPDA)?;
     }
 
+    let mint_account = next_mint_account(stake_account, &game_state.token_mint, account_iter)?;
+
+    // Ticket tokens in circulation are still owed their value
+    release_ticket_tokens(&mut game_state, account_iter)?;
+
     // We transfer the remaining balance to the owner
 
     general_transfer(
@@ -118,26 +123,15 @@ pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
         None,
         pda_account,
         &[&[game_account.key.as_ref(), &[bump_seed]]],
-        token_program,
+        mint_account,
     )?;
 
     if !is_native_mint(&game_state.token_mint) {
         msg!("Close stake account");
-        let close_ix = close_account(
-            token_program.key,
-            stake_account.key,
-            owner_account.key,
-            pda_account.key,
-            &[pda_account.key],
-        )?;
-
-        invoke_signed(
-            &close_ix,
-            &[
-                stake_account.clone(),
-                owner_account.clone(),
-                pda_account.clone(),
-            ],
+        close_token_account(
+            stake_account,
+            owner_account,
+            pda_account,
             &[&[game_account.key.as_ref(), &[bump_seed]]],
         )?;
     }
@@ -149,14 +143,20 @@ pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
         game_account,
         pda_account,
         bump_seed,
-        token_program,
         account_iter,
     )?;
 
-    game_account.realloc(1, false)?;
-    let data = &mut game_account.try_borrow_mut_data()?;
-    data[0] = 2;
-    let minimum_rent = Rent::get()?.minimum_balance(1);
+    // Keep the owner and versions, so the account can be reopened for a new session
+    let closed_state = ClosedGameState {
+        game_status: GameStatus::Closed,
+        owner: game_state.owner,
+        access_version: game_state.access_version,
+        settle_version: game_state.settle_version,
+    };
+    let closed_data_len = borsh::object_length(&closed_state)?;
+    game_account.realloc(closed_data_len, false)?;
+    borsh::to_writer(&mut game_account.try_borrow_mut_data()?[..], &closed_state)?;
+    let minimum_rent = Rent::get()?.minimum_balance(closed_data_len);
 
     // Close players reg account and transafer the SOL to the owner
     // Close game account and transfer the SOL to the owner
@@ -174,5 +174,8 @@ pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     **players_reg_account.lamports.borrow_mut() = 0;
 
     msg!("Successfully closed the game account: {}", game_account.key);
+
+    RaceEvent::GameClosed { game: *game_account.key }.emit()?;
+
     Ok(())
 }
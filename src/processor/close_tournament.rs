@@ -0,0 +1,198 @@
+//! Close a finished tournament.
+//!
+//! Signed by the tournament owner once the prizes are paid.  The rest of the prize pool, e.g. the
+//! entries left by unpaid places, is transferred to the owner, and the tournament account is
+//! closed.
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::ProcessError,
+    events::RaceEvent,
+    processor::misc::{
+        close_program_account, close_token_account, general_transfer, is_native_mint, next_mint_account, validate_receiver,
+    },
+    state::TournamentState,
+};
+
+#[inline(never)]
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_iter = &mut accounts.iter();
+
+    let owner_account = next_account_info(account_iter)?;
+
+    let tournament_account = next_account_info(account_iter)?;
+
+    let stake_account = next_account_info(account_iter)?;
+
+    let pda_account = next_account_info(account_iter)?;
+
+    let receiver_account = next_account_info(account_iter)?;
+
+    let _token_program = next_account_info(account_iter)?;
+
+    let _system_program = next_account_info(account_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if tournament_account.owner.ne(program_id) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let tournament_state = TournamentState::try_from_slice(&tournament_account.try_borrow_data()?)?;
+    if !tournament_state.is_initialized || !tournament_state.is_finished {
+        msg!("Only a settled tournament can be closed");
+        return Err(ProcessError::InvalidTournament)?;
+    }
+
+    if tournament_state.owner.ne(owner_account.key) {
+        return Err(ProcessError::InvalidOwner)?;
+    }
+
+    // Satellite tickets into the games are refunded once the tournament is finished
+    if tournament_state.ticket_funds > 0 {
+        msg!("Tickets worth {} are not refunded yet", tournament_state.ticket_funds);
+        return Err(ProcessError::OutstandingFunds)?;
+    }
+
+    if stake_account.key.ne(&tournament_state.stake_account) {
+        return Err(ProcessError::InvalidStakeAccount)?;
+    }
+
+    let (pda, bump_seed) = Pubkey::find_program_address(&[tournament_account.key.as_ref()], program_id);
+    if pda.ne(pda_account.key) {
+        return Err(ProcessError::InvalidPDA)?;
+    }
+
+    validate_receiver(owner_account.key, &tournament_state.token_mint, receiver_account.key)?;
+
+    let mint_account = next_mint_account(stake_account, &tournament_state.token_mint, account_iter)?;
+
+    if tournament_state.prize_pool > 0 {
+        general_transfer(
+            stake_account,
+            receiver_account,
+            &tournament_state.token_mint,
+            Some(tournament_state.prize_pool),
+            pda_account,
+            &[&[tournament_account.key.as_ref(), &[bump_seed]]],
+            mint_account,
+        )?;
+    }
+
+    if !is_native_mint(&tournament_state.token_mint) {
+        close_token_account(
+            stake_account,
+            owner_account,
+            pda_account,
+            &[&[tournament_account.key.as_ref(), &[bump_seed]]],
+        )?;
+    }
+
+    close_program_account(tournament_account, owner_account)?;
+
+    msg!("Closed tournament {}, withdrew {}", tournament_account.key, tournament_state.prize_pool);
+
+    RaceEvent::TournamentClosed {
+        tournament: *tournament_account.key,
+        amount: tournament_state.prize_pool,
+    }
+    .emit()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::ProcessError,
+        instruction::RaceInstruction,
+        testing::{process_instruction, tournament_state, TestAccount},
+    };
+    use solana_program::pubkey::Pubkey;
+
+    #[test]
+    fn test_close_tournament() {
+        let program_id = Pubkey::new_unique();
+        let tournament_key = Pubkey::new_unique();
+        let (pda, _) = Pubkey::find_program_address(&[tournament_key.as_ref()], &program_id);
+
+        let mut tournament_state = tournament_state(&[Pubkey::new_unique()], &[], 100);
+        let mut owner = TestAccount::wallet(0).with_key(tournament_state.owner).signer();
+        tournament_state.stake_account = pda;
+        let mut tournament =
+            TestAccount::program_account(&program_id, borsh::to_vec(&tournament_state).unwrap()).with_key(tournament_key);
+        let tournament_rent = tournament.lamports;
+        let mut stake = TestAccount::wallet(100).with_key(pda);
+        let mut pda_account = stake.clone();
+        let mut receiver = owner.clone();
+        let mut token_program = TestAccount::wallet(0);
+        let mut system_program = TestAccount::system_program();
+
+        // The prizes are not paid yet
+        let result = process_instruction(
+            &program_id,
+            &mut [
+                &mut owner,
+                &mut tournament,
+                &mut stake,
+                &mut pda_account,
+                &mut receiver,
+                &mut token_program,
+                &mut system_program,
+            ],
+            RaceInstruction::CloseTournament,
+        );
+        assert_eq!(result, Err(ProcessError::InvalidTournament.into()));
+
+        tournament_state.is_finished = true;
+        tournament.data = borsh::to_vec(&tournament_state).unwrap();
+
+        // Only the owner withdraws
+        let mut other = TestAccount::wallet(0).signer();
+        let mut other_receiver = other.clone();
+        let result = process_instruction(
+            &program_id,
+            &mut [
+                &mut other,
+                &mut tournament,
+                &mut stake,
+                &mut pda_account,
+                &mut other_receiver,
+                &mut token_program,
+                &mut system_program,
+            ],
+            RaceInstruction::CloseTournament,
+        );
+        assert_eq!(result, Err(ProcessError::InvalidOwner.into()));
+
+        process_instruction(
+            &program_id,
+            &mut [
+                &mut owner,
+                &mut tournament,
+                &mut stake,
+                &mut pda_account,
+                &mut receiver,
+                &mut token_program,
+                &mut system_program,
+            ],
+            RaceInstruction::CloseTournament,
+        )
+        .unwrap();
+
+        assert_eq!(owner.lamports, 100 + tournament_rent);
+        assert_eq!(stake.lamports, 0);
+        assert_eq!(tournament.lamports, 0);
+        assert!(tournament.data.is_empty());
+    }
+}
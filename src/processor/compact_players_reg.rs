@@ -0,0 +1,190 @@
+//! Compact the players reg account.
+//!
+//! Permissionless.  Live players are packed to the front without changing their ids, empty slots
+//! are dropped, and the lamports above the rent-exempt minimum are refunded to the game owner.
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+};
+
+use crate::{
+    error::ProcessError,
+    events::RaceEvent,
+    state::{players, GameState},
+};
+
+#[inline(never)]
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_iter = &mut accounts.iter();
+
+    let payer_account = next_account_info(account_iter)?;
+
+    let game_account = next_account_info(account_iter)?;
+
+    let players_reg_account = next_account_info(account_iter)?;
+
+    let owner_account = next_account_info(account_iter)?;
+
+    if !payer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if game_account.owner.ne(program_id) || players_reg_account.owner.ne(program_id) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    if !matches!(game_account.data.borrow()[0], 1 | 3) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
+
+    if players_reg_account.key.ne(&game_state.players_reg_account) {
+        return Err(ProcessError::InvalidPlayersRegAccount)?;
+    }
+
+    if owner_account.key.ne(&game_state.owner) {
+        return Err(ProcessError::InvalidOwner)?;
+    }
+
+    let old_len = players_reg_account.data_len();
+    let new_len = players::compact(&mut players_reg_account.try_borrow_mut_data()?)?;
+    players_reg_account.realloc(new_len, false)?;
+
+    let minimum_rent = Rent::get()?.minimum_balance(new_len);
+    let refund = players_reg_account.lamports().saturating_sub(minimum_rent);
+
+    **owner_account.lamports.borrow_mut() = owner_account
+        .lamports()
+        .checked_add(refund)
+        .ok_or(ProcessError::StakeAmountOverflow)?;
+    **players_reg_account.lamports.borrow_mut() -= refund;
+
+    msg!("Compacted players reg account {} -> {} bytes, refund {} lamports", old_len, new_len, refund);
+
+    RaceEvent::PlayersRegCompacted {
+        game: *game_account.key,
+        refund,
+    }
+    .emit()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::ProcessError,
+        instruction::RaceInstruction,
+        state::{players, GameState, GameStatus, PlayerJoin},
+        testing::{players_reg_data, process_instruction, ticket_game, TestAccount},
+    };
+    use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey, rent::Rent};
+
+    fn compact(
+        program_id: &Pubkey,
+        payer: &mut TestAccount,
+        game: &mut TestAccount,
+        players_reg: &mut TestAccount,
+        owner: &mut TestAccount,
+    ) -> ProgramResult {
+        process_instruction(program_id, &mut [payer, game, players_reg, owner], RaceInstruction::CompactPlayersReg)
+    }
+
+    #[test]
+    fn test_compact_players_reg() {
+        let program_id = Pubkey::new_unique();
+        let mut payer = TestAccount::wallet(0).signer();
+        let mut owner = TestAccount::wallet(0);
+        let players: Vec<PlayerJoin> = (0..3)
+            .map(|i| PlayerJoin { addr: Pubkey::new_unique(), position: i, access_version: i as u64 + 1 })
+            .collect();
+        let mut data = players_reg_data(6, &players);
+        players::remove_player_by_index(&mut data, 0).unwrap();
+        players::remove_player_by_index(&mut data, 1).unwrap();
+        let mut players_reg = TestAccount::program_account(&program_id, data);
+        let reg_lamports = players_reg.lamports;
+
+        let mut game_state = ticket_game(100, None, None);
+        game_state.owner = owner.key;
+        game_state.players_reg_account = players_reg.key;
+        let mut game = TestAccount::program_account(&program_id, borsh::to_vec(&game_state).unwrap());
+
+        compact(&program_id, &mut payer, &mut game, &mut players_reg, &mut owner).unwrap();
+
+        // Only the last player is kept, with its id
+        let data = &players_reg.data;
+        assert_eq!(data.len(), players::head_len(6) + players::slots_len(1));
+        assert_eq!(players::get_slots_count(data).unwrap(), 1);
+        assert_eq!(players::get_players_count(data).unwrap(), 1);
+        let (index, player) = players::get_player_by_id(data, 3).unwrap().unwrap();
+        assert_eq!(index, 0);
+        assert_eq!(player.addr, players[2].addr);
+        assert!(players::get_player_by_id(data, 1).unwrap().is_none());
+        assert!(players::is_position_occupied(data, 2).unwrap());
+        assert!(!players::is_position_occupied(data, 0).unwrap());
+
+        // The rent above the minimum goes to the owner
+        assert_eq!(players_reg.lamports, Rent::default().minimum_balance(data.len()));
+        assert_eq!(owner.lamports + players_reg.lamports, reg_lamports);
+
+        // Nothing is left to compact
+        compact(&program_id, &mut payer, &mut game, &mut players_reg, &mut owner).unwrap();
+        assert_eq!(owner.lamports + players_reg.lamports, reg_lamports);
+    }
+
+    #[test]
+    fn test_compact_players_reg_failures() {
+        let program_id = Pubkey::new_unique();
+        let mut payer = TestAccount::wallet(0).signer();
+        let mut owner = TestAccount::wallet(0);
+        let players = [PlayerJoin { addr: Pubkey::new_unique(), position: 0, access_version: 1 }];
+        let mut players_reg = TestAccount::program_account(&program_id, players_reg_data(6, &players));
+        let game_state = GameState {
+            owner: owner.key,
+            players_reg_account: players_reg.key,
+            ..ticket_game(100, None, None)
+        };
+        let mut game = TestAccount::program_account(&program_id, borsh::to_vec(&game_state).unwrap());
+
+        // The refund only goes to the game owner
+        let mut other = TestAccount::wallet(0);
+        assert_eq!(
+            compact(&program_id, &mut payer, &mut game, &mut players_reg, &mut other),
+            Err(ProcessError::InvalidOwner.into())
+        );
+
+        // The players reg must be the one of the game
+        let mut other_reg = TestAccount::program_account(&program_id, players_reg_data(6, &players));
+        assert_eq!(
+            compact(&program_id, &mut payer, &mut game, &mut other_reg, &mut owner),
+            Err(ProcessError::InvalidPlayersRegAccount.into())
+        );
+
+        // A foreign players reg can't be resized
+        players_reg.owner = Pubkey::new_unique();
+        assert_eq!(
+            compact(&program_id, &mut payer, &mut game, &mut players_reg, &mut owner),
+            Err(ProgramError::InvalidAccountOwner)
+        );
+        players_reg.owner = program_id;
+
+        // Closed and cancelled games are not compacted
+        game.data = borsh::to_vec(&GameState { game_status: GameStatus::Cancelled, ..game_state }).unwrap();
+        assert_eq!(
+            compact(&program_id, &mut payer, &mut game, &mut players_reg, &mut owner),
+            Err(ProgramError::UninitializedAccount)
+        );
+
+        payer.is_signer = false;
+        assert_eq!(
+            compact(&program_id, &mut payer, &mut game, &mut players_reg, &mut owner),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+        assert_eq!(owner.lamports, 0);
+    }
+}
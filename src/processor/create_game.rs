@@ -6,21 +6,25 @@ use solana_program::{
     sysvar::Sysvar,
     program::invoke,
     program_error::ProgramError,
-    program_pack::Pack,
     pubkey::Pubkey,
 };
 
-use crate::state::{players, GameStatus};
+use crate::state::{players, EntryType, GameStatus};
 use crate::state::GameState;
 use crate::{
     error::ProcessError,
-    processor::misc::{is_native_mint, pack_state_to_account},
+    events::RaceEvent,
+    processor::misc::{
+        is_native_mint, is_token_program, pack_state_to_account, resize_account, unpack_mint,
+        unpack_token_account,
+    },
     state::EntryLock,
     types::CreateGameAccountParams,
 };
-use spl_token::{
+use spl_token_2022::{
+    extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions},
     instruction::{set_authority, AuthorityType},
-    state::{Account, Mint},
+    state::Mint,
 };
 
 #[inline(never)]
@@ -30,6 +34,31 @@ pub fn process(
     params: CreateGameAccountParams,
 ) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
+
+    let payer = next_account_info(accounts_iter)?;
+
+    let game_account = next_account_info(accounts_iter)?;
+
+    init_game(program_id, accounts, params, 0, 0)?;
+
+    RaceEvent::GameCreated {
+        game: *game_account.key,
+        owner: *payer.key,
+    }
+    .emit()
+}
+
+/// Write a fresh game state to the game account, with versions starting from the given ones.
+/// Shared by game creation and reopening.
+#[inline(never)]
+pub fn init_game(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: CreateGameAccountParams,
+    access_version: u64,
+    settle_version: u64,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
     let payer = next_account_info(accounts_iter)?;
 
     if !payer.is_signer {
@@ -57,6 +86,32 @@ pub fn process(
     }
     let recipient_addr = recipient_account.key.to_owned();
 
+    if let EntryType::Allowlist { inner, .. } = &params.entry_type {
+        if matches!(inner.as_ref(), EntryType::Allowlist { .. }) {
+            msg!("Nested allowlist is not supported");
+            return Err(ProcessError::InvalidEntryType)?;
+        }
+    }
+
+    if let EntryType::Cash { deposit_step: Some(0), .. } = params.entry_type.payment_type() {
+        msg!("Deposit step must be greater than zero");
+        return Err(ProcessError::InvalidEntryType)?;
+    }
+
+    if let EntryType::Ticket { amount, fee: Some(fee), .. } = params.entry_type.payment_type() {
+        if *fee == 0 || fee >= amount {
+            msg!("Ticket fee {} must be greater than zero and less than the ticket amount {}", fee, amount);
+            return Err(ProcessError::InvalidEntryType)?;
+        }
+    }
+
+    if let (Some(close_time), Some(late_until)) = (params.registration_close_time, params.late_registration_until) {
+        if late_until < close_time {
+            msg!("Late registration ends at {}, before registration closes at {}", late_until, close_time);
+            return Err(ProcessError::InvalidSchedule)?;
+        }
+    }
+
     let (pda_stake, _bump_seed_stake) = Pubkey::find_program_address(&[game_account.key.as_ref()], program_id);
 
     if game_account.owner.ne(&program_id) {
@@ -75,38 +130,7 @@ pub fn process(
         return Err(ProgramError::AccountNotRentExempt);
     }
 
-    if is_native_mint(&token_account.key) {
-        // For SOL, use PDA as stake account.
-        if pda_stake.ne(&stake_account.key) {
-            msg!("For game account with native token, the stake account must be a PDA.");
-            return Err(ProcessError::InvalidStakeAccount)?;
-        }
-    } else {
-        // For SPL, use dedicated stake account.
-        let token_state = Mint::unpack_unchecked(&token_account.data.borrow())?;
-        if !token_state.is_initialized {
-            return Err(ProcessError::InvalidTokenMint)?;
-        }
-
-        let stake_state = Account::unpack(&stake_account.data.borrow())?;
-        if stake_state.mint.ne(&token_account.key) {
-            return Err(ProcessError::InvalidStakeAccount)?;
-        }
-
-        let set_authority_ix = set_authority(
-            token_program.key,
-            stake_account.key,
-            Some(&pda_stake),
-            AuthorityType::AccountOwner,
-            payer.key,
-            &[&payer.key],
-        )?;
-
-        invoke(
-            &set_authority_ix,
-            &[stake_account.clone(), payer.clone(), token_program.clone()],
-        )?;
-    }
+    init_stake_account(&pda_stake, stake_account, token_account, token_program, payer)?;
 
     let game_state = GameState {
         // is_initialized: true,
@@ -118,8 +142,8 @@ pub fn process(
         owner: payer.key.clone(),
         transactor_addr: None,
         token_mint: *token_account.key,
-        access_version: 0,
-        settle_version: 0,
+        access_version,
+        settle_version,
         max_players: params.max_players,
         data_len: params.data.len() as u32,
         data: params.data,
@@ -134,11 +158,105 @@ pub fn process(
         entry_lock: EntryLock::Open,
         bonuses: Default::default(),
         balances: Default::default(),
+        start_time: params.start_time,
+        registration_close_time: params.registration_close_time,
+        late_registration_until: params.late_registration_until,
+        buyins: Default::default(),
+        invite_key: params.invite_key,
+        gatekeeper: params.gatekeeper,
+        sponsor_funds: 0,
+        tournament: None,
+        ticket_funds: 0,
+        ticket_mint: None,
+        presold_funds: 0,
+        ticket_token_value: None,
+        start_settle_version: settle_version,
     };
 
+    resize_account(players_reg_account, players::head_len(params.max_players), payer, system_program)?;
+    players::init_account_data(&mut players_reg_account.try_borrow_mut_data()?, params.max_players)?;
+    players::set_versions(&mut players_reg_account.try_borrow_mut_data()?, access_version, settle_version)?;
+
     msg!("Created game account: {:?}", game_account.key);
 
     pack_state_to_account(game_state, &game_account, &payer, &system_program)?;
 
     Ok(())
 }
+
+/// Validate the stake account for the mint, and transfer its ownership to the PDA.  For SOL, the
+/// PDA itself is the stake account.
+pub fn init_stake_account<'a>(
+    pda_stake: &Pubkey,
+    stake_account: &AccountInfo<'a>,
+    token_account: &AccountInfo<'a>,
+    token_program: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+) -> ProgramResult {
+    if is_native_mint(&token_account.key) {
+        // For SOL, use PDA as stake account.
+        if pda_stake.ne(stake_account.key) {
+            msg!("For native token, the stake account must be a PDA.");
+            return Err(ProcessError::InvalidStakeAccount)?;
+        }
+    } else {
+        // For SPL, use dedicated stake account, of either token program.
+        if !is_token_program(token_program.key) || token_account.owner.ne(token_program.key) {
+            return Err(ProcessError::InvalidTokenMint)?;
+        }
+
+        validate_mint_extensions(token_account)?;
+
+        let token_state = unpack_mint(token_account)?;
+        if !token_state.is_initialized {
+            return Err(ProcessError::InvalidTokenMint)?;
+        }
+
+        let stake_state = unpack_token_account(stake_account)?;
+        if stake_state.mint.ne(&token_account.key) {
+            return Err(ProcessError::InvalidStakeAccount)?;
+        }
+
+        // A reopened game may keep its stake account, which is already owned by the PDA
+        if stake_state.owner.ne(pda_stake) {
+            let set_authority_ix = set_authority(
+                token_program.key,
+                stake_account.key,
+                Some(pda_stake),
+                AuthorityType::AccountOwner,
+                payer.key,
+                &[&payer.key],
+            )?;
+
+            invoke(
+                &set_authority_ix,
+                &[stake_account.clone(), payer.clone(), token_program.clone()],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Token-2022 mints whose transfers need more than the mint account are not supported, e.g.
+/// transfer hooks requiring extra accounts, or confidential and non-transferable tokens.  Mints
+/// with a permanent delegate are rejected too, as the delegate could move the stake.
+pub(crate) fn validate_mint_extensions(mint_account: &AccountInfo) -> ProgramResult {
+    if mint_account.owner.ne(&spl_token_2022::id()) {
+        return Ok(());
+    }
+    let mint_data = mint_account.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+    for extension in mint_state.get_extension_types()? {
+        if matches!(
+            extension,
+            ExtensionType::TransferHook
+                | ExtensionType::ConfidentialTransferMint
+                | ExtensionType::NonTransferable
+                | ExtensionType::PermanentDelegate
+        ) {
+            msg!("Unsupported mint extension: {:?}", extension);
+            return Err(ProcessError::InvalidTokenMint)?;
+        }
+    }
+    Ok(())
+}
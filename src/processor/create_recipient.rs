@@ -4,16 +4,14 @@ use solana_program::{
     msg,
     program::invoke,
     program_error::ProgramError,
-    program_pack::Pack,
     pubkey::Pubkey,
 };
-use spl_token::{
-    instruction::{set_authority, AuthorityType},
-    state::Account,
-};
+use spl_token_2022::instruction::{set_authority, AuthorityType};
 
 use crate::{
-    error::ProcessError, processor::misc::{is_native_mint, pack_state_to_account},
+    error::ProcessError,
+    processor::create_game::validate_mint_extensions,
+    processor::misc::{is_native_mint, next_mint_account, pack_state_to_account, unpack_token_account},
     state::{RecipientSlot, RecipientState}, types::CreateRecipientParams,
 };
 
@@ -55,13 +53,16 @@ pub fn process(
                 return Err(ProcessError::InvalidSlotStakeAccount)?;
             }
         } else {
-            let stake_account_state = Account::unpack(&slot_stake_account.try_borrow_data()?)?;
+            let stake_account_state = unpack_token_account(slot_stake_account)?;
             if stake_account_state.mint.ne(&slot.token_addr) {
                 return Err(ProgramError::InvalidArgument);
             }
+            if let Some(mint_account) = next_mint_account(slot_stake_account, &slot.token_addr, accounts_iter)? {
+                validate_mint_extensions(mint_account)?;
+            }
             // Transfer the authority to PDA account
             let set_authority_ix = set_authority(
-                token_program.key,
+                slot_stake_account.owner,
                 slot_stake_account.key,
                 Some(&pda),
                 AuthorityType::AccountOwner,
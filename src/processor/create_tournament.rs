@@ -0,0 +1,104 @@
+//! Create a tournament, which groups several games under one prize pool.
+//!
+//! The entries of all linked games are collected in the stake account of the tournament, and the
+//! prizes are paid from it with `SettleTournament` by the final table, set with `SetFinalGame`.
+//! What's left is withdrawn by the owner with `CloseTournament`.
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::ProcessError,
+    events::RaceEvent,
+    state::{EntryType, TournamentState},
+    types::CreateTournamentParams,
+};
+
+use super::{create_game::init_stake_account, misc::pack_state_to_account};
+
+#[inline(never)]
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], params: CreateTournamentParams) -> ProgramResult {
+    let account_iter = &mut accounts.iter();
+
+    let payer = next_account_info(account_iter)?;
+
+    let tournament_account = next_account_info(account_iter)?;
+
+    let stake_account = next_account_info(account_iter)?;
+
+    let token_account = next_account_info(account_iter)?;
+
+    let token_program = next_account_info(account_iter)?;
+
+    let system_program = next_account_info(account_iter)?;
+
+    if !payer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if tournament_account.owner.ne(program_id) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    if !tournament_account.data_is_empty() && tournament_account.data.borrow()[0] != 0 {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    if !matches!(params.entry_type.payment_type(), EntryType::Ticket { .. } | EntryType::Free) {
+        msg!("Only ticket and freeroll tournaments are supported");
+        return Err(ProcessError::InvalidEntryType)?;
+    }
+
+    if let EntryType::Ticket { amount, fee: Some(fee), .. } = params.entry_type.payment_type() {
+        if *fee == 0 || fee >= amount {
+            msg!("Ticket fee {} must be greater than zero and less than the ticket amount {}", fee, amount);
+            return Err(ProcessError::InvalidEntryType)?;
+        }
+    }
+
+    if let (Some(close_time), Some(late_until)) = (params.registration_close_time, params.late_registration_until) {
+        if late_until < close_time {
+            msg!("Late registration ends at {}, before registration closes at {}", late_until, close_time);
+            return Err(ProcessError::InvalidSchedule)?;
+        }
+    }
+
+    let (pda_stake, _bump_seed) = Pubkey::find_program_address(&[tournament_account.key.as_ref()], program_id);
+
+    init_stake_account(&pda_stake, stake_account, token_account, token_program, payer)?;
+
+    let tournament_state = TournamentState {
+        is_initialized: true,
+        is_finished: false,
+        title: params.title,
+        owner: *payer.key,
+        token_mint: *token_account.key,
+        stake_account: *stake_account.key,
+        entry_type: params.entry_type,
+        start_time: params.start_time,
+        registration_close_time: params.registration_close_time,
+        late_registration_until: params.late_registration_until,
+        games: Default::default(),
+        final_game: None,
+        players: Default::default(),
+        prize_pool: 0,
+        ticket_funds: 0,
+    };
+
+    pack_state_to_account(tournament_state, tournament_account, payer, system_program)?;
+
+    msg!("Created tournament account: {:?}", tournament_account.key);
+
+    RaceEvent::TournamentCreated {
+        tournament: *tournament_account.key,
+        owner: *payer.key,
+    }
+    .emit()?;
+
+    Ok(())
+}
@@ -1,11 +1,10 @@
+use crate::events::RaceEvent;
 use crate::state::players;
-use crate::{error::ProcessError, processor::misc::pack_state_to_account, state::{DepositStatus, EntryType, GameState, PlayerDeposit}, types::DepositParams};
-use borsh::BorshDeserialize;
+use crate::{error::ProcessError, processor::misc::{collect_payment, next_tournament_account, pack_state_to_account, validate_fee_receiver}, state::{DepositStatus, EntryType, GameState, PlayerDeposit}, types::DepositParams};
 ///! Player joins a game (cash, sng or tourney)
 use solana_program::{
-    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program::invoke, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey, rent::Rent
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey, rent::Rent
 };
-use spl_token::{instruction::{close_account, transfer}, native_mint, state::Account};
 
 #[inline(never)]
 pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], params: DepositParams) -> ProgramResult {
@@ -28,7 +27,7 @@ pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], params: DepositPa
 
     let _pda_account = next_account_info(account_iter)?;
 
-    let token_program = next_account_info(account_iter)?;
+    let _token_program = next_account_info(account_iter)?;
 
     let system_program = next_account_info(account_iter)?;
 
@@ -38,20 +37,24 @@ pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], params: DepositPa
 
     let rent = Rent::default();
 
-    if !Rent::is_exempt(&rent, player_account.lamports(), player_account.data_len()) {
+    // A player funded by others may hold no lamports at all
+    if player_account.key.eq(payer_account.key) && !Rent::is_exempt(&rent, player_account.lamports(), player_account.data_len()) {
         return Err(ProgramError::AccountNotRentExempt);
     }
 
-    if game_account.data.borrow()[0] != 1 {
-        return Err(ProgramError::UninitializedAccount);
+    match game_account.data.borrow()[0] {
+        1 => (),
+        3 => return Err(ProcessError::GamePaused)?,
+        _ => return Err(ProgramError::UninitializedAccount),
     }
-    let mut game_state = GameState::try_from_slice(&game_account.try_borrow_data()?)?;
+    let mut game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
 
     if game_state.settle_version < params.settle_version {
         return Err(ProcessError::InvalidSettleVersion)?;
     }
 
-    if game_state.stake_account.ne(stake_account.key) {
+    // The deposits of tournament games go to the tournament stake account, checked below
+    if game_state.tournament.is_none() && game_state.stake_account.ne(stake_account.key) {
         return Err(ProcessError::InvalidStakeAccount)?;
     }
 
@@ -63,29 +66,29 @@ pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], params: DepositPa
         return Err(ProcessError::PlayerNotInGame)?;
     }
 
-    let is_native_token = game_state.token_mint.eq(&native_mint::id());
-
-    match &game_state.entry_type {
-        EntryType::Cash {
-            min_deposit, max_deposit
-        } => {
+    match game_state.entry_type.payment_type() {
+        EntryType::Cash { .. } => {
 
             // For Cash games, the deposit amount must be between `min_deposit` and `max_deposit`.
-
-            if params.amount < *min_deposit || params.amount > *max_deposit {
-                msg!(
-                    "Invalid deposit amount: {}, min: {}, max: {}",
-                    params.amount,
-                    min_deposit,
-                    max_deposit
-                );
-                return Err(ProcessError::InvalidPaymentParams)?;
-            }
+            // With table stakes, the current balance, the pending deposits and this deposit
+            // together can't exceed `max_deposit`.
+            let balance = players::get_player_by_addr(&players_reg_account.try_borrow_data()?, player_account.key)?
+                .and_then(|(_, p)| game_state.balances.iter().find(|b| b.player_id == p.access_version))
+                .map(|b| b.balance)
+                .unwrap_or_default();
+            let pending = game_state
+                .deposits
+                .iter()
+                .filter(|d| d.addr.eq(player_account.key) && d.status == DepositStatus::Pending)
+                .map(|d| d.amount)
+                .sum::<u64>();
+            game_state.entry_type.validate_cash_deposit(params.amount, balance + pending)?;
         },
-        EntryType::Ticket { amount } => {
+        EntryType::Ticket { amount, rebuy, .. } => {
 
-            // For Ticket games, the deposit amount must be equal to ticket amonut.
-            if params.amount != *amount {
+            // For Ticket games, the deposit amount must be equal to ticket amonut, or the add-on amount.
+            let is_addon = rebuy.as_ref().is_some_and(|r| r.addon_amount == Some(params.amount));
+            if params.amount != *amount && !is_addon {
                 msg!("Invalid payment amount: {}, ticket: {}",
                     params.amount, amount);
 
@@ -93,72 +96,56 @@ pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], params: DepositPa
             }
 
         },
+        EntryType::Free => {
+
+            // Rebuys are not available in freerolls
+            msg!("Deposit is not allowed in freeroll");
+            return Err(ProcessError::InvalidPaymentParams)?;
+        },
         _ => { unimplemented!() }
     }
 
-        if !is_native_token {
-        // For SPL tokens, use token program to transfer tokens
-        let temp_state = Account::unpack(&temp_account.try_borrow_data()?)?;
-
-        if temp_state.amount != params.amount {
-            msg!("Required amount: {}, actual amount: {}", params.amount, temp_state.amount);
-            return Err(ProcessError::InvalidDeposit)?;
-        }
-
-        let transfer_ix = transfer(
-            token_program.key,
-            temp_account.key,
-            stake_account.key,
-            payer_account.key,
-            &[&payer_account.key],
-            params.amount as u64,
-        )?;
-
-        invoke(
-            &transfer_ix,
-            &[
-                temp_account.clone(),
-                stake_account.clone(),
-                payer_account.clone(),
-                token_program.clone(),
-            ],
-        )?;
-
-        msg!("Close temp account.");
-        let close_temp_account_ix = close_account(
-            token_program.key,
-            temp_account.key,
-            payer_account.key,
-            payer_account.key,
-            &[&payer_account.key],
-        )?;
-
-        invoke(
-            &close_temp_account_ix,
-            &[
-                temp_account.clone(),
-                payer_account.clone(),
-                payer_account.clone(),
-            ],
+    game_state.add_ticket_buyin(player_account.key, params.amount)?;
+
+    // The fee part of a ticket is routed to the recipient slot at once
+    let fee = game_state.entry_type.ticket_fee(params.amount);
+    let fee_receiver = if fee > 0 {
+        let recipient_account = next_account_info(account_iter)?;
+        let fee_receiver_account = next_account_info(account_iter)?;
+        validate_fee_receiver(
+            recipient_account,
+            &game_state.recipient_addr,
+            &game_state.token_mint,
+            fee_receiver_account.key,
         )?;
+        Some((fee_receiver_account, fee))
     } else {
-        // For native mint, just close the account, transfer its lamports to stake account
-        if temp_account.lamports() != params.amount {
-            msg!("Invalid deposit, required: {}, actual: {}", params.amount, temp_account.lamports());
-            return Err(ProcessError::InvalidDeposit)?;
-        }
-
-        **(stake_account.try_borrow_mut_lamports()?) += temp_account.lamports();
-        **(temp_account.try_borrow_mut_lamports()?) = 0;
+        None
+    };
 
+    let tournament = next_tournament_account(&game_state, account_iter)?;
+    if let Some((_, tournament_state)) = &tournament {
+        if tournament_state.stake_account.ne(stake_account.key) {
+            return Err(ProcessError::InvalidStakeAccount)?;
+        }
     }
 
+    // The amount received by the stake account, after the fees
+    let received = collect_payment(
+        payer_account,
+        temp_account,
+        stake_account,
+        fee_receiver,
+        mint_account,
+        params.amount,
+    )?;
+
     // Increase game access version
     game_state.access_version += 1;
 
     game_state.deposits.push(PlayerDeposit {
-        addr: payer_account.key.clone(),
-        amount: params.amount,
+        addr: player_account.key.clone(),
+        amount: received,
         access_version: game_state.access_version,
         settle_version: params.settle_version,
         status: DepositStatus::Pending,
@@ -166,10 +153,26 @@ pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], params: DepositPa
 
     players::set_versions(&mut players_reg_account.try_borrow_mut_data()?, game_state.access_version, game_state.settle_version)?;
 
-    pack_state_to_account(game_state, &game_account, &player_account, &system_program)?;
+    let access_version = game_state.access_version;
+
+    pack_state_to_account(game_state, &game_account, &payer_account, &system_program)?;
+
+    if let Some((tournament_account, mut tournament_state)) = tournament {
+        tournament_state.prize_pool += received;
+        pack_state_to_account(tournament_state, tournament_account, payer_account, system_program)?;
+    }
+
+    RaceEvent::Deposited {
+        game: *game_account.key,
+        player: *player_account.key,
+        access_version,
+        amount: received,
+    }
+    .emit()?;
 
     msg!(
-        "Player {} deposited to game",
+        "Player {} deposited to game, paid by {}",
+        player_account.key,
         payer_account.key,
     );
 
@@ -1,5 +1,10 @@
-use crate::processor::misc::{append_state_to_account, pack_state_to_account};
-use crate::state::{DepositStatus, PlayerDeposit, RecipientState};
+use crate::events::RaceEvent;
+use crate::processor::misc::{
+    append_state_to_account, close_program_account, collect_payment, is_join_approved, is_token_program,
+    next_tournament_account, pack_state_to_account, unpack_token_account, validate_fee_receiver,
+    verify_invite_signature, verify_merkle_proof,
+};
+use crate::state::{DepositStatus, PlayerDeposit, RecipientState, TicketState};
 use crate::types::JoinParams;
 use crate::state::players;
 use crate::{
@@ -9,22 +14,18 @@ use crate::{
 use borsh::BorshDeserialize;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    clock::Clock,
     entrypoint::ProgramResult,
     msg,
-    program::invoke,
     program_error::ProgramError,
-    program_pack::Pack,
     pubkey::Pubkey,
-    sysvar::rent::Rent,
-};
-use spl_token::{
-    instruction::{close_account, transfer},
-    native_mint,
-    state::Account,
+    program::invoke,
+    sysvar::{rent::Rent, Sysvar},
 };
+use spl_token_2022::instruction::burn;
 
 #[inline(never)]
-pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], params: JoinParams) -> ProgramResult {
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], params: JoinParams) -> ProgramResult {
 
     let account_iter = &mut accounts.into_iter();
 
@@ -56,7 +57,8 @@ pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], params: JoinParam
 
     let rent = Rent::default();
 
-    if !rent.is_exempt(player_account.lamports(), player_account.data_len()) {
+    // A player funded by others may hold no lamports at all
+    if player_account.key.eq(payer_account.key) && !rent.is_exempt(player_account.lamports(), player_account.data_len()) {
         return Err(ProgramError::AccountNotRentExempt);
     }
 
@@ -76,16 +78,19 @@ pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], params: JoinParam
 
     msg!("Deserializing game state, data len: {}", game_account.data_len());
 
-    if game_account.data.borrow()[0] != 1 {
-        return Err(ProgramError::UninitializedAccount);
+    match game_account.data.borrow()[0] {
+        1 => (),
+        3 => return Err(ProcessError::GamePaused)?,
+        _ => return Err(ProgramError::UninitializedAccount),
     }
-    let mut game_state = GameState::try_from_slice(&game_account.try_borrow_data()?)?;
+    let mut game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
 
     if game_state.settle_version < params.settle_version {
         return Err(ProcessError::InvalidSettleVersion)?;
     }
 
-    if game_state.stake_account.ne(stake_account.key) {
+    // The entries of tournament games go to the tournament stake account, checked below
+    if game_state.tournament.is_none() && game_state.stake_account.ne(stake_account.key) {
         return Err(ProcessError::InvalidStakeAccount)?;
     }
 
@@ -93,6 +98,14 @@ pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], params: JoinParam
         return Err(ProcessError::InvalidMint)?;
     }
 
+    if let Some(deadline) = game_state.registration_deadline() {
+        let now = Clock::get()?.unix_timestamp as u64;
+        if now >= deadline {
+            msg!("Registration closed at {}, now: {}", deadline, now);
+            return Err(ProcessError::RegistrationClosed)?;
+        }
+    }
+
     // 1. game already full?
     // 2. position within [0..=(len-1)]?
     // 3. player already joined?
@@ -120,11 +133,15 @@ pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], params: JoinParam
     // Increase game access version
     game_state.access_version += 1;
 
-    let is_native_token = game_state.token_mint.eq(&native_mint::id());
+    if let EntryType::Allowlist { merkle_root, .. } = &game_state.entry_type {
+        if !verify_merkle_proof(merkle_root, player_account.key, &params.proof) {
+            return Err(ProcessError::NotInAllowlist)?;
+        }
+    }
 
-    match &game_state.entry_type {
+    match game_state.entry_type.payment_type() {
         EntryType::Cash {
-            min_deposit, max_deposit
+            min_deposit, max_deposit, deposit_step, ..
         } => {
             if params.amount < *min_deposit || params.amount > *max_deposit {
                 msg!(
@@ -135,8 +152,12 @@ pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], params: JoinParam
                 );
                 return Err(ProcessError::InvalidPaymentParams)?;
             }
+            if deposit_step.is_some_and(|step| !params.amount.is_multiple_of(step)) {
+                msg!("Deposit amount {} is not a multiple of {:?}", params.amount, deposit_step);
+                return Err(ProcessError::InvalidPaymentParams)?;
+            }
         },
-        EntryType::Ticket { amount } => {
+        EntryType::Ticket { amount, .. } => {
             if params.amount != *amount {
                 msg!("Invalid payment amount: {}, ticket: {}",
                     params.amount, amount);
@@ -145,70 +166,134 @@ pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], params: JoinParam
             }
 
         },
+        EntryType::Free => {
+            if params.amount != 0 {
+                msg!("Invalid payment amount: {}, freeroll", params.amount);
+                return Err(ProcessError::InvalidPaymentParams)?;
+            }
+        },
         _ => { unimplemented!() }
     }
 
-    if !is_native_token {
-        // For SPL tokens, use token program to transfer tokens
-        let temp_state = Account::unpack(&temp_account.try_borrow_data()?)?;
-
-        if temp_state.amount != params.amount {
-            msg!("Required amount: {}, actual amount: {}", params.amount, temp_state.amount);
-            return Err(ProcessError::InvalidDeposit)?;
+    game_state.add_ticket_buyin(player_account.key, params.amount)?;
+
+    // A ticket awarded by a satellite is redeemed in place of the payment, its value is held in
+    // the stake account already
+    let ticket = if temp_account.owner.eq(program_id) {
+        let ticket_state = TicketState::try_from_slice(&temp_account.try_borrow_data()?)?;
+        let (ticket_pda, _) = TicketState::find_address(program_id, game_account.key, player_account.key);
+        if !ticket_state.is_initialized
+            || ticket_pda.ne(temp_account.key)
+            || !matches!(game_state.entry_type.payment_type(), EntryType::Ticket { .. })
+        {
+            return Err(ProcessError::InvalidTicket)?;
         }
+        Some(ticket_state)
+    } else {
+        None
+    };
 
-        let transfer_ix = transfer(
-            token_program.key,
-            temp_account.key,
-            stake_account.key,
-            payer_account.key,
-            &[&payer_account.key],
-            params.amount as u64,
-        )?;
-
-        invoke(
-            &transfer_ix,
-            &[
-                temp_account.clone(),
-                stake_account.clone(),
-                payer_account.clone(),
-                token_program.clone(),
-            ],
-        )?;
-
-        msg!("Close temp account.");
-        let close_temp_account_ix = close_account(
-            token_program.key,
-            temp_account.key,
-            payer_account.key,
-            payer_account.key,
-            &[&payer_account.key],
-        )?;
+    // A ticket token bought in advance is burned in place of the payment
+    let is_ticket_token = game_state.ticket_mint.is_some_and(|ticket_mint| {
+        is_token_program(temp_account.owner)
+            && unpack_token_account(temp_account).is_ok_and(|t| t.mint.eq(&ticket_mint))
+    });
 
-        invoke(
-            &close_temp_account_ix,
-            &[
-                temp_account.clone(),
-                payer_account.clone(),
-                payer_account.clone(),
-            ],
+    // The fee part of a ticket is routed to the recipient slot at once, it's not charged again
+    // for a redeemed ticket
+    let fee = if ticket.is_some() || is_ticket_token {
+        0
+    } else {
+        game_state.entry_type.ticket_fee(params.amount)
+    };
+    let fee_receiver = if fee > 0 {
+        let fee_receiver_account = next_account_info(account_iter)?;
+        validate_fee_receiver(
+            recipient_account,
+            &game_state.recipient_addr,
+            &game_state.token_mint,
+            fee_receiver_account.key,
         )?;
+        Some((fee_receiver_account, fee))
     } else {
-        // For native mint, just close the account, transfer its lamports to stake account
-        if temp_account.lamports() != params.amount {
-            msg!("Invalid deposit, required: {}, actual: {}", params.amount, temp_account.lamports());
-            return Err(ProcessError::InvalidDeposit)?;
+        None
+    };
+
+    if let Some(gatekeeper) = &game_state.gatekeeper {
+        let approval_account = next_account_info(account_iter)?;
+        if !is_join_approved(approval_account, gatekeeper, game_account.key, player_account.key)? {
+            return Err(ProcessError::JoinNotApproved)?;
         }
+    }
 
-        **(stake_account.try_borrow_mut_lamports()?) += temp_account.lamports();
-        **(temp_account.try_borrow_mut_lamports()?) = 0;
+    if let Some(invite_key) = &game_state.invite_key {
+        let instructions_sysvar = next_account_info(account_iter)?;
+        if !verify_invite_signature(instructions_sysvar, invite_key, game_account.key, player_account.key)? {
+            return Err(ProcessError::InvalidInviteCode)?;
+        }
+    }
 
+    let mut tournament = next_tournament_account(&game_state, account_iter)?;
+    if let Some((_, tournament_state)) = &mut tournament {
+        if tournament_state.stake_account.ne(stake_account.key) {
+            return Err(ProcessError::InvalidStakeAccount)?;
+        }
+        tournament_state.add_player(player_account.key, game_account.key)?;
     }
 
+    let ticket_mint_account = if is_ticket_token {
+        let ticket_mint_account = next_account_info(account_iter)?;
+        if game_state.ticket_mint.ne(&Some(*ticket_mint_account.key)) || token_program.key.ne(temp_account.owner) {
+            return Err(ProcessError::InvalidTicket)?;
+        }
+        Some(ticket_mint_account)
+    } else {
+        None
+    };
+
+    // The amount received by the stake account, after the fees
+    let received = if let Some(ticket_state) = ticket {
+        let rent_payer_account = next_account_info(account_iter)?;
+        if rent_payer_account.key.ne(&ticket_state.rent_payer) {
+            return Err(ProcessError::InvalidTicket)?;
+        }
+        // The funds of tickets into tournament games are held by the tournament
+        let ticket_funds = match &mut tournament {
+            Some((_, tournament_state)) => &mut tournament_state.ticket_funds,
+            None => &mut game_state.ticket_funds,
+        };
+        *ticket_funds = ticket_funds
+            .checked_sub(ticket_state.amount)
+            .ok_or(ProcessError::InvalidTicket)?;
+        close_program_account(temp_account, rent_payer_account)?;
+        msg!("Redeemed ticket awarded by {}", ticket_state.source_game_addr);
+        ticket_state.amount
+    } else if let Some(ticket_mint_account) = ticket_mint_account {
+        let value = game_state.redeem_ticket_token()?;
+        invoke(
+            &burn(token_program.key, temp_account.key, ticket_mint_account.key, payer_account.key, &[], 1)?,
+            &[temp_account.clone(), ticket_mint_account.clone(), payer_account.clone(), token_program.clone()],
+        )?;
+        msg!("Burned a ticket token worth {}", value);
+        value
+    } else if params.amount == 0 {
+        msg!("No payment required for freeroll");
+        0
+    } else {
+        collect_payment(
+            payer_account,
+            temp_account,
+            stake_account,
+            fee_receiver,
+            mint_account,
+            params.amount,
+        )?
+    };
+
     msg!("Add player and its deposit to game state");
 
     let player_join = PlayerJoin {
-        addr: payer_account.key.clone(),
+        addr: player_account.key.clone(),
         position,
         access_version: game_state.access_version,
     };
@@ -220,8 +305,8 @@ pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], params: JoinParam
     }
 
     game_state.deposits.push(PlayerDeposit {
-        addr: payer_account.key.clone(),
-        amount: params.amount,
+        addr: player_account.key.clone(),
+        amount: received,
         access_version: game_state.access_version,
         settle_version: params.settle_version,
         status: DepositStatus::Pending,
@@ -231,8 +316,23 @@ pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], params: JoinParam
 
     pack_state_to_account(game_state, &game_account, &payer_account, &system_program)?;
 
+    if let Some((tournament_account, mut tournament_state)) = tournament {
+        tournament_state.prize_pool += received;
+        pack_state_to_account(tournament_state, tournament_account, payer_account, system_program)?;
+    }
+
+    RaceEvent::PlayerJoined {
+        game: *game_account.key,
+        player: *player_account.key,
+        position: player_join.position,
+        access_version: player_join.access_version,
+        amount: received,
+    }
+    .emit()?;
+
     msg!(
-        "Player {} joined game",
+        "Player {} joined game, paid by {}",
+        player_account.key,
         payer_account.key,
     );
 
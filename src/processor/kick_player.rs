@@ -0,0 +1,183 @@
+//! Remove a player who has nothing at stake, without a settlement.
+//!
+//! Signed by the transactor or the game owner.  The player must hold no balance and have no
+//! pending deposit, e.g. an idle joiner whose deposit was never accepted.  The access version is
+//! bumped, so the transactor picks up the removal the same way it picks up new joins.  A player
+//! kicked from a tournament game leaves the tournament as well.
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::ProcessError,
+    events::RaceEvent,
+    state::{players, DepositStatus, GameState},
+    types::KickPlayerParams,
+};
+
+use super::misc::{next_tournament_account, pack_state_to_account};
+
+#[inline(never)]
+pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], params: KickPlayerParams) -> ProgramResult {
+    let account_iter = &mut accounts.iter();
+
+    let signer_account = next_account_info(account_iter)?;
+
+    let game_account = next_account_info(account_iter)?;
+
+    let players_reg_account = next_account_info(account_iter)?;
+
+    let system_program = next_account_info(account_iter)?;
+
+    if !signer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // Paused games can still be cleaned up
+    if !matches!(game_account.data.borrow()[0], 1 | 3) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let mut game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
+
+    if game_state.owner.ne(signer_account.key)
+        && game_state.transactor_addr.ne(&Some(*signer_account.key))
+    {
+        return Err(ProcessError::InvalidOwner)?;
+    }
+
+    if players_reg_account.key.ne(&game_state.players_reg_account) {
+        return Err(ProcessError::InvalidPlayersRegAccount)?;
+    }
+
+    let tournament = next_tournament_account(&game_state, account_iter)?;
+
+    let mut data = players_reg_account.try_borrow_mut_data()?;
+
+    let (index, player) = players::get_player_by_id(&data, params.player_id)?
+        .ok_or(ProcessError::PlayerNotInGame)?;
+
+    if game_state
+        .balances
+        .iter()
+        .any(|b| b.player_id == params.player_id && b.balance > 0)
+    {
+        msg!("Player {} holds a balance", player.addr);
+        return Err(ProcessError::PlayerNotKickable)?;
+    }
+
+    if game_state
+        .deposits
+        .iter()
+        .any(|d| d.addr.eq(&player.addr) && d.status == DepositStatus::Pending)
+    {
+        msg!("Player {} has a pending deposit", player.addr);
+        return Err(ProcessError::PlayerNotKickable)?;
+    }
+
+    players::remove_player_by_index(&mut data, index)?;
+
+    game_state.access_version += 1;
+
+    players::set_versions(&mut data, game_state.access_version, game_state.settle_version)?;
+
+    drop(data);
+
+    let access_version = game_state.access_version;
+
+    pack_state_to_account(game_state, game_account, signer_account, system_program)?;
+
+    if let Some((tournament_account, mut tournament_state)) = tournament {
+        tournament_state.remove_player(&player.addr);
+        pack_state_to_account(tournament_state, tournament_account, signer_account, system_program)?;
+    }
+
+    RaceEvent::PlayerKicked {
+        game: *game_account.key,
+        player: player.addr,
+        access_version,
+    }
+    .emit()?;
+
+    msg!("Player {} kicked from game", player.addr);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::ProcessError,
+        instruction::RaceInstruction,
+        state::{players, PlayerBalance, PlayerJoin, TournamentState},
+        testing::{players_reg_data, process_instruction, ticket_game, tournament_state, TestAccount},
+        types::KickPlayerParams,
+    };
+    use solana_program::pubkey::Pubkey;
+
+    fn kick(player_id: u64) -> RaceInstruction {
+        RaceInstruction::KickPlayer { params: KickPlayerParams { player_id } }
+    }
+
+    #[test]
+    fn test_kick_tournament_player() {
+        let program_id = Pubkey::new_unique();
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        let game_key = Pubkey::new_unique();
+        let reg_key = Pubkey::new_unique();
+        let tournament_key = Pubkey::new_unique();
+
+        let mut transactor = TestAccount::wallet(1_000_000_000).signer();
+        let mut game_state = ticket_game(100, None, None);
+        game_state.transactor_addr = Some(transactor.key);
+        game_state.players_reg_account = reg_key;
+        game_state.tournament = Some(tournament_key);
+        game_state.access_version = 2;
+        game_state.balances.push(PlayerBalance { player_id: 2, balance: 100 });
+        let mut game = TestAccount::program_account(&program_id, borsh::to_vec(&game_state).unwrap()).with_key(game_key);
+        let players = [
+            PlayerJoin { addr: alice, position: 0, access_version: 1 },
+            PlayerJoin { addr: bob, position: 1, access_version: 2 },
+        ];
+        let mut reg = TestAccount::program_account(&program_id, players_reg_data(6, &players)).with_key(reg_key);
+        let tournament_data = borsh::to_vec(&tournament_state(&[game_key], &[alice, bob], 200)).unwrap();
+        let mut tournament = TestAccount::program_account(&program_id, tournament_data).with_key(tournament_key);
+        let mut system_program = TestAccount::system_program();
+
+        // Bob holds a balance
+        let result = process_instruction(
+            &program_id,
+            &mut [&mut transactor, &mut game, &mut reg, &mut system_program, &mut tournament],
+            kick(2),
+        );
+        assert_eq!(result, Err(ProcessError::PlayerNotKickable.into()));
+
+        process_instruction(
+            &program_id,
+            &mut [&mut transactor, &mut game, &mut reg, &mut system_program, &mut tournament],
+            kick(1),
+        )
+        .unwrap();
+
+        assert_eq!(game.game_state().access_version, 3);
+        assert!(!players::is_player_joined(&reg.data, &alice).unwrap());
+        assert!(players::is_player_joined(&reg.data, &bob).unwrap());
+        let tournament_state: TournamentState = tournament.state();
+        assert_eq!(tournament_state.players.len(), 1);
+        assert_eq!(tournament_state.players[0].addr, bob);
+
+        // Only the owner and the transactor can kick
+        let mut stranger = TestAccount::wallet(1_000_000_000).signer();
+        let result = process_instruction(
+            &program_id,
+            &mut [&mut stranger, &mut game, &mut reg, &mut system_program, &mut tournament],
+            kick(2),
+        );
+        assert_eq!(result, Err(ProcessError::InvalidOwner.into()));
+    }
+}
@@ -0,0 +1,72 @@
+//! Migrate a legacy players reg account to the versioned layout.
+//!
+//! Available to the game owner and the transactor, who pays for the extra rent if any.  Players,
+//! slots and versions are kept, the position flags are sized by `max_players` of the game.
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::ProcessError,
+    events::RaceEvent,
+    state::{players, GameState},
+};
+
+use super::misc::resize_account;
+
+#[inline(never)]
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_iter = &mut accounts.iter();
+
+    let signer_account = next_account_info(account_iter)?;
+
+    let game_account = next_account_info(account_iter)?;
+
+    let players_reg_account = next_account_info(account_iter)?;
+
+    let system_program = next_account_info(account_iter)?;
+
+    if !signer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if game_account.owner.ne(program_id) || players_reg_account.owner.ne(program_id) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    if !matches!(game_account.data.borrow()[0], 1 | 3) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
+
+    if game_state.owner.ne(signer_account.key)
+        && game_state.transactor_addr.ne(&Some(*signer_account.key))
+    {
+        return Err(ProcessError::InvalidOwner)?;
+    }
+
+    if players_reg_account.key.ne(&game_state.players_reg_account) {
+        return Err(ProcessError::InvalidPlayersRegAccount)?;
+    }
+
+    if players::get_layout_version(&players_reg_account.try_borrow_data()?)? == players::LAYOUT_VERSION {
+        msg!("Players reg account is migrated already");
+        return Ok(());
+    }
+
+    let new_data = players::migrate_legacy(&players_reg_account.try_borrow_data()?, game_state.max_players)?;
+
+    resize_account(players_reg_account, new_data.len(), signer_account, system_program)?;
+    players_reg_account.try_borrow_mut_data()?.copy_from_slice(&new_data);
+
+    msg!("Migrated players reg account, {} bytes", new_data.len());
+
+    RaceEvent::PlayersRegMigrated { game: *game_account.key }.emit()?;
+
+    Ok(())
+}
@@ -3,21 +3,39 @@ use std::str::FromStr;
 
 use borsh::BorshSerialize;
 use solana_program::{
-    account_info::AccountInfo,
+    account_info::{next_account_info, AccountInfo},
+    ed25519_program,
     entrypoint::ProgramResult,
+    hash::hashv,
     msg,
+    clock::Clock,
     program::{invoke, invoke_signed},
-    program_pack::Pack,
+    program_error::ProgramError,
     pubkey::Pubkey,
     rent::Rent,
     system_instruction,
-    sysvar::Sysvar,
+    sysvar::{
+        self,
+        instructions::{load_current_index_checked, load_instruction_at_checked},
+        Sysvar,
+    },
 };
 
-use spl_associated_token_account::get_associated_token_address;
-use spl_token::{instruction::transfer, state::Account};
+use borsh::BorshDeserialize;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token_2022::{
+    extension::{
+        transfer_fee::{TransferFeeAmount, TransferFeeConfig},
+        BaseStateWithExtensions, StateWithExtensions,
+    },
+    instruction::{close_account, transfer_checked},
+    state::{Account, Mint},
+};
 
-use crate::error::ProcessError;
+use crate::{
+    error::ProcessError,
+    state::{GameState, RecipientState, TournamentState},
+};
 
 const NATIVE_MINT: &str = "So11111111111111111111111111111111111111112";
 
@@ -25,8 +43,319 @@ pub fn is_native_mint(mint: &Pubkey) -> bool {
     mint.eq(&Pubkey::from_str(NATIVE_MINT).unwrap())
 }
 
+/// Either the SPL token program or the Token-2022 program.
+pub fn is_token_program(program_id: &Pubkey) -> bool {
+    program_id.eq(&spl_token::id()) || program_id.eq(&spl_token_2022::id())
+}
+
+/// Unpack a token account of either token program, the extensions of Token-2022 are skipped.
+pub fn unpack_token_account(account: &AccountInfo) -> Result<Account, ProgramError> {
+    Ok(StateWithExtensions::<Account>::unpack(&account.try_borrow_data()?)?.base)
+}
+
+/// Unpack a mint of either token program, the extensions of Token-2022 are skipped.
+pub fn unpack_mint(account: &AccountInfo) -> Result<Mint, ProgramError> {
+    Ok(StateWithExtensions::<Mint>::unpack(&account.try_borrow_data()?)?.base)
+}
+
+/// The fee withheld by the mint when transferring `amount`, zero for mints without the transfer
+/// fee extension.
+pub fn transfer_fee(mint_account: &AccountInfo, amount: u64) -> Result<u64, ProgramError> {
+    let mint_data = mint_account.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+    let Ok(fee_config) = mint_state.get_extension::<TransferFeeConfig>() else {
+        return Ok(0);
+    };
+    fee_config
+        .calculate_epoch_fee(Clock::get()?.epoch, amount)
+        .ok_or(ProgramError::ArithmeticOverflow)
+}
+
+/// The fee to add on top of a transfer, so the destination receives `amount` after the fee
+/// withheld by the mint.  Zero for mints without the transfer fee extension.
+pub fn inverse_transfer_fee(mint_account: &AccountInfo, amount: u64) -> Result<u64, ProgramError> {
+    let mint_data = mint_account.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<Mint>::unpack(&mint_data)?;
+    let Ok(fee_config) = mint_state.get_extension::<TransferFeeConfig>() else {
+        return Ok(0);
+    };
+    fee_config
+        .calculate_inverse_epoch_fee(Clock::get()?.epoch, amount)
+        .ok_or(ProgramError::ArithmeticOverflow)
+}
+
+/// Transfer tokens with the program which owns the source account.  With a mint account given,
+/// `transfer_checked` is used, which is required by Token-2022.  Return the amount received by
+/// the destination, after transfer fees.
+#[inline(never)]
+pub fn token_transfer<'a>(
+    source_account: &AccountInfo<'a>,
+    dest_account: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    mint_account: Option<&AccountInfo<'a>>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<u64, ProgramError> {
+    let token_program_id = source_account.owner;
+
+    let Some(mint_account) = mint_account else {
+        if token_program_id.eq(&spl_token_2022::id()) {
+            return Err(ProcessError::MintAccountRequired)?;
+        }
+        let ix = spl_token::instruction::transfer(
+            token_program_id,
+            source_account.key,
+            dest_account.key,
+            authority.key,
+            &[authority.key],
+            amount,
+        )?;
+        invoke_signed(
+            &ix,
+            &[source_account.clone(), dest_account.clone(), authority.clone()],
+            signer_seeds,
+        )?;
+        return Ok(amount);
+    };
+
+    let decimals = unpack_mint(mint_account)?.decimals;
+    let fee = transfer_fee(mint_account, amount)?;
+
+    let ix = transfer_checked(
+        token_program_id,
+        source_account.key,
+        mint_account.key,
+        dest_account.key,
+        authority.key,
+        &[authority.key],
+        amount,
+        decimals,
+    )?;
+    invoke_signed(
+        &ix,
+        &[
+            source_account.clone(),
+            mint_account.clone(),
+            dest_account.clone(),
+            authority.clone(),
+        ],
+        signer_seeds,
+    )?;
+
+    Ok(amount - fee)
+}
+
+/// Take the mint account from `account_iter` when `token_account` is owned by Token-2022, as its
+/// transfers require the mint.  No account is taken for SPL token and SOL.
+pub fn next_mint_account<'a, 'b, I: Iterator<Item = &'a AccountInfo<'b>>>(
+    token_account: &AccountInfo<'b>,
+    mint: &Pubkey,
+    account_iter: &mut I,
+) -> Result<Option<&'a AccountInfo<'b>>, ProgramError> {
+    if token_account.owner.ne(&spl_token_2022::id()) {
+        return Ok(None);
+    }
+    let mint_account = next_account_info(account_iter)?;
+    if mint_account.key.ne(mint) {
+        return Err(ProcessError::InvalidMint)?;
+    }
+    Ok(Some(mint_account))
+}
+
+/// Take the tournament account from `account_iter` when the game is a table of a tournament.
+/// The tournament must be open for joins.
+pub fn next_tournament_account<'a, 'b, I: Iterator<Item = &'a AccountInfo<'b>>>(
+    game_state: &GameState,
+    account_iter: &mut I,
+) -> Result<Option<(&'a AccountInfo<'b>, TournamentState)>, ProgramError> {
+    let Some(tournament) = &game_state.tournament else {
+        return Ok(None);
+    };
+    let tournament_account = next_account_info(account_iter)?;
+    if tournament_account.key.ne(tournament) {
+        return Err(ProcessError::InvalidTournament)?;
+    }
+    let tournament_state = TournamentState::try_from_slice(&tournament_account.try_borrow_data()?)?;
+    if !tournament_state.is_open() {
+        return Err(ProcessError::InvalidTournament)?;
+    }
+    Ok(Some((tournament_account, tournament_state)))
+}
+
+/// Take the ticket mint account from `account_iter` when the game sells ticket tokens, and
+/// release the pre-sold funds.  Fails while any ticket token is in circulation, its holder must
+/// join the game to redeem it first, or get it refunded once expired.
+pub fn release_ticket_tokens<'a, 'b: 'a, I: Iterator<Item = &'a AccountInfo<'b>>>(
+    game_state: &mut GameState,
+    account_iter: &mut I,
+) -> ProgramResult {
+    let Some(ticket_mint) = game_state.ticket_mint else {
+        return Ok(());
+    };
+    let ticket_mint_account = next_account_info(account_iter)?;
+    if ticket_mint_account.key.ne(&ticket_mint) {
+        return Err(ProcessError::InvalidMint)?;
+    }
+    let supply = unpack_mint(ticket_mint_account)?.supply;
+    game_state.release_presold_funds(supply)
+}
+
+/// Close a token account with the program which owns it.  Token-2022 accounts holding withheld
+/// transfer fees can't be closed until the fees are harvested, they are left open.
+#[inline(never)]
+pub fn close_token_account<'a>(
+    account: &AccountInfo<'a>,
+    dest_account: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    {
+        let data = account.try_borrow_data()?;
+        let state = StateWithExtensions::<Account>::unpack(&data)?;
+        if state.get_extension::<TransferFeeAmount>().is_ok_and(|f| f.closable().is_err()) {
+            msg!("Account {} holds withheld transfer fees, leave it open", account.key);
+            return Ok(());
+        }
+    }
+
+    let ix = close_account(
+        account.owner,
+        account.key,
+        dest_account.key,
+        authority.key,
+        &[authority.key],
+    )?;
+    invoke_signed(
+        &ix,
+        &[account.clone(), dest_account.clone(), authority.clone()],
+        signer_seeds,
+    )?;
+
+    Ok(())
+}
+
+/// Verify a Merkle proof of an account.  Leaves are the hashes of account pubkeys, and each pair of
+/// nodes is hashed in sorted order, so the proof doesn't need to carry the directions.
+pub fn verify_merkle_proof(root: &[u8; 32], account_key: &Pubkey, proof: &[[u8; 32]]) -> bool {
+    let mut node = hashv(&[account_key.as_ref()]).to_bytes();
+    for sibling in proof.iter() {
+        node = if node <= *sibling {
+            hashv(&[&node, sibling]).to_bytes()
+        } else {
+            hashv(&[sibling, &node]).to_bytes()
+        };
+    }
+    node.eq(root)
+}
+
+/// Check if the gatekeeper of a game approves `player` to join, by either signing the transaction
+/// or signing the game and player addresses with an ed25519 instruction before this one.  The
+/// `approval_account` is the gatekeeper or the instructions sysvar accordingly.
+pub fn is_join_approved(
+    approval_account: &AccountInfo,
+    gatekeeper: &Pubkey,
+    game_key: &Pubkey,
+    player_key: &Pubkey,
+) -> Result<bool, ProgramError> {
+    if approval_account.key.eq(gatekeeper) {
+        Ok(approval_account.is_signer)
+    } else {
+        has_join_signature(approval_account, gatekeeper, game_key, player_key)
+    }
+}
+
+/// Check if the invite key of a game approves `player` to join, by signing the game and player
+/// addresses with an ed25519 instruction before this one.  The invite key is derived from the
+/// invite secret off-chain, the secret itself never appears in a transaction, and a signature is
+/// bound to one player so it can't be reused by others.
+pub fn verify_invite_signature(
+    instructions_sysvar: &AccountInfo,
+    invite_key: &Pubkey,
+    game_key: &Pubkey,
+    player_key: &Pubkey,
+) -> Result<bool, ProgramError> {
+    has_join_signature(instructions_sysvar, invite_key, game_key, player_key)
+}
+
+/// Check if `signer` signs the game and player addresses with an ed25519 instruction before this
+/// one, read from the instructions sysvar.
+fn has_join_signature(
+    instructions_sysvar: &AccountInfo,
+    signer: &Pubkey,
+    game_key: &Pubkey,
+    player_key: &Pubkey,
+) -> Result<bool, ProgramError> {
+    if instructions_sysvar.key.ne(&sysvar::instructions::id()) {
+        return Ok(false);
+    }
+    let message = [game_key.as_ref(), player_key.as_ref()].concat();
+    has_ed25519_signature(instructions_sysvar, signer, &message)
+}
+
+// Layout of the ed25519 program instruction data
+const ED25519_HEADER_LEN: usize = 2;
+const ED25519_OFFSETS_LEN: usize = 14;
+const ED25519_PUBKEY_LEN: usize = 32;
+
+fn read_u16(data: &[u8], offset: usize) -> Option<usize> {
+    let bytes = data.get(offset..(offset + 2))?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]) as usize)
+}
+
+/// Check if an earlier instruction of the transaction verifies `message` signed by `signer`, with
+/// the ed25519 program.  Only signatures with all data in the ed25519 instruction itself are
+/// accepted.
+#[inline(never)]
+pub fn has_ed25519_signature(
+    instructions_sysvar: &AccountInfo,
+    signer: &Pubkey,
+    message: &[u8],
+) -> Result<bool, ProgramError> {
+    let current_index = load_current_index_checked(instructions_sysvar)?;
+
+    for index in 0..current_index {
+        let ix = load_instruction_at_checked(index as usize, instructions_sysvar)?;
+        if ix.program_id.ne(&ed25519_program::id()) || ix.data.len() < ED25519_HEADER_LEN {
+            continue;
+        }
+        let num_signatures = ix.data[0] as usize;
+        for i in 0..num_signatures {
+            let start = ED25519_HEADER_LEN + i * ED25519_OFFSETS_LEN;
+            let (
+                Some(signature_ix_index),
+                Some(pubkey_offset),
+                Some(pubkey_ix_index),
+                Some(message_offset),
+                Some(message_size),
+                Some(message_ix_index),
+            ) = (
+                read_u16(&ix.data, start + 2),
+                read_u16(&ix.data, start + 4),
+                read_u16(&ix.data, start + 6),
+                read_u16(&ix.data, start + 8),
+                read_u16(&ix.data, start + 10),
+                read_u16(&ix.data, start + 12),
+            )
+            else {
+                break;
+            };
+            let this_ix = u16::MAX as usize;
+            if signature_ix_index != this_ix || pubkey_ix_index != this_ix || message_ix_index != this_ix {
+                continue;
+            }
+            let pubkey = ix.data.get(pubkey_offset..(pubkey_offset + ED25519_PUBKEY_LEN));
+            let signed_message = ix.data.get(message_offset..(message_offset + message_size));
+            if pubkey == Some(signer.as_ref()) && signed_message == Some(message) {
+                return Ok(true);
+            }
+        }
+    }
+
+    Ok(false)
+}
+
 /// Validate if the receiver is owned by account.
-/// For SPL token, the receiver must be an ATA of account for mint.
+/// For SPL token, the receiver must be an ATA of account for mint, of either token program.
 /// For SOL, the receiver must be account.
 #[inline(never)]
 pub fn validate_receiver(
@@ -43,8 +372,9 @@ pub fn validate_receiver(
             );
         }
     } else {
-        let ata = get_associated_token_address(account_key, mint);
-        if receiver_key.ne(&ata) {
+        let ata = get_associated_token_address_with_program_id(account_key, mint, &spl_token::id());
+        let ata_2022 = get_associated_token_address_with_program_id(account_key, mint, &spl_token_2022::id());
+        if receiver_key.ne(&ata) && receiver_key.ne(&ata_2022) {
             msg!(
                 "Invalid receiver, expected: {:?}, actual: {:?}",
                 ata,
@@ -61,35 +391,22 @@ pub fn transfer_spl<'a>(
     source_account: AccountInfo<'a>,
     dest_account: AccountInfo<'a>,
     pda: AccountInfo<'a>,
-    token_program: &AccountInfo<'a>,
+    mint_account: Option<&AccountInfo<'a>>,
     amount: Option<u64>,
     signer_seeds: &[&[&[u8]]],
 ) -> ProgramResult {
-    if Account::unpack(&dest_account.try_borrow_data()?).is_err() {
+    if unpack_token_account(&dest_account).is_err() {
         return Err(ProcessError::ReceiverUninitialized)?;
     }
 
     let amount = match amount {
         Some(amount) => amount,
-        None => Account::unpack(&source_account.try_borrow_data()?)?.amount,
+        None => unpack_token_account(&source_account)?.amount,
     };
 
-    let ix = transfer(
-        token_program.key,
-        source_account.key,
-        dest_account.key,
-        &pda.key,
-        &[pda.key],
-        amount,
-    )?;
-
     msg!("Transfer {} SPL to {}", amount, dest_account.key);
 
-    invoke_signed(
-        &ix,
-        &[source_account.clone(), dest_account.clone(), pda.clone()],
-        signer_seeds,
-    )?;
+    token_transfer(&source_account, &dest_account, &pda, mint_account, amount, signer_seeds)?;
 
     Ok(())
 }
@@ -124,7 +441,7 @@ pub fn general_transfer<'a>(
     amount: Option<u64>,
     pda: &AccountInfo<'a>,
     signer_seeds: &[&[&[u8]]],
-    token_program: &AccountInfo<'a>,
+    mint_account: Option<&AccountInfo<'a>>,
 ) -> ProgramResult {
     if is_native_mint(mint) {
         transfer_sol(
@@ -138,7 +455,7 @@ pub fn general_transfer<'a>(
             source_account.to_owned(),
             dest_account.to_owned(),
             pda.to_owned(),
-            token_program,
+            mint_account,
             amount,
             signer_seeds,
         )?;
@@ -146,6 +463,112 @@ pub fn general_transfer<'a>(
     Ok(())
 }
 
+/// Validate the account to receive the fee of a payment, it must be the stake account of the
+/// recipient slot for game token.
+#[inline(never)]
+pub fn validate_fee_receiver(
+    recipient_account: &AccountInfo,
+    recipient_addr: &Pubkey,
+    mint: &Pubkey,
+    receiver_key: &Pubkey,
+) -> ProgramResult {
+    if recipient_account.key.ne(recipient_addr) {
+        return Err(ProcessError::InvalidRecipientAddress)?;
+    }
+    let recipient_state = RecipientState::try_from_slice(&recipient_account.try_borrow_data()?)?;
+    let Some(slot) = recipient_state.slots.iter().find(|s| s.token_addr.eq(mint)) else {
+        return Err(ProcessError::RecipientSlotNotFound)?;
+    };
+    if slot.stake_addr.ne(receiver_key) {
+        return Err(ProcessError::InvalidSlotStakeAccount)?;
+    }
+    Ok(())
+}
+
+/// Check if the payment is made directly from the payer's own account, rather than a temp
+/// account.  That is the payer's ATA for SPL tokens, or the payer itself for SOL.
+pub fn is_direct_payment(payer_key: &Pubkey, source_key: &Pubkey, mint: &Pubkey) -> bool {
+    if is_native_mint(mint) {
+        return source_key.eq(payer_key);
+    }
+    [spl_token::id(), spl_token_2022::id()]
+        .iter()
+        .any(|program_id| source_key.eq(&get_associated_token_address_with_program_id(payer_key, mint, program_id)))
+}
+
+/// Collect a player's payment.  The source is either a temp account which must hold exactly
+/// `amount` and is closed afterwards, or the payer's own account, see [`is_direct_payment`].  The
+/// fee part goes to the fee receiver, the rest goes to the stake account.  Return the amount
+/// received by the stake account, after transfer fees of the mint.
+#[inline(never)]
+pub fn collect_payment<'a>(
+    payer_account: &AccountInfo<'a>,
+    source_account: &AccountInfo<'a>,
+    stake_account: &AccountInfo<'a>,
+    fee_receiver: Option<(&AccountInfo<'a>, u64)>,
+    mint_account: &AccountInfo<'a>,
+    amount: u64,
+) -> Result<u64, ProgramError> {
+    let fee = fee_receiver.map(|(_, fee)| fee).unwrap_or_default();
+    let stake_amount = amount.checked_sub(fee).ok_or(ProcessError::InvalidPaymentParams)?;
+    let is_direct = is_direct_payment(payer_account.key, source_account.key, mint_account.key);
+
+    if !is_native_mint(mint_account.key) {
+        // For SPL tokens, use token program to transfer tokens
+        let source_state = unpack_token_account(source_account)?;
+
+        if is_direct && source_state.amount < amount {
+            msg!("Required amount: {}, balance: {}", amount, source_state.amount);
+            return Err(ProcessError::InvalidDeposit)?;
+        } else if !is_direct && source_state.amount != amount {
+            msg!("Required amount: {}, actual amount: {}", amount, source_state.amount);
+            return Err(ProcessError::InvalidDeposit)?;
+        }
+
+        if let Some((receiver, fee)) = fee_receiver {
+            token_transfer(source_account, receiver, payer_account, Some(mint_account), fee, &[])?;
+        }
+
+        let received = token_transfer(
+            source_account,
+            stake_account,
+            payer_account,
+            Some(mint_account),
+            stake_amount,
+            &[],
+        )?;
+
+        if !is_direct {
+            msg!("Close temp account.");
+            close_token_account(source_account, payer_account, payer_account, &[])?;
+        }
+
+        Ok(received)
+    } else if is_direct {
+        // For SOL paid by the payer, use system transfers
+        transfer_sol(payer_account.clone(), stake_account.clone(), Some(stake_amount), &[])?;
+        if let Some((receiver, fee)) = fee_receiver {
+            transfer_sol(payer_account.clone(), receiver.clone(), Some(fee), &[])?;
+        }
+
+        Ok(stake_amount)
+    } else {
+        // For native mint, just close the account, transfer its lamports to stake account
+        if source_account.lamports() != amount {
+            msg!("Invalid deposit, required: {}, actual: {}", amount, source_account.lamports());
+            return Err(ProcessError::InvalidDeposit)?;
+        }
+
+        **(stake_account.try_borrow_mut_lamports()?) += stake_amount;
+        if let Some((receiver, fee)) = fee_receiver {
+            **(receiver.try_borrow_mut_lamports()?) += fee;
+        }
+        **(source_account.try_borrow_mut_lamports()?) = 0;
+
+        Ok(stake_amount)
+    }
+}
+
 #[inline(never)]
 pub fn append_state_to_account<'a, T: BorshSerialize>(
     state: &T,
@@ -155,9 +578,76 @@ pub fn append_state_to_account<'a, T: BorshSerialize>(
 ) -> ProgramResult {
     let old_len = account.data_len();
     let new_data_len = borsh::object_length(&state)?;
-    account.realloc(account.data_len() + new_data_len, false)?;
+    extend_account(account, new_data_len, payer, system_program)?;
+    borsh::to_writer(&mut account.try_borrow_mut_data()?[old_len..], &state)?;
+    Ok(())
+}
+
+/// Grow the account by `extra_len` zeroed bytes, the payer tops up the lamports for rent-exempt.
+#[inline(never)]
+pub fn extend_account<'a>(
+    account: &AccountInfo<'a>,
+    extra_len: usize,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+) -> ProgramResult {
+    resize_account(account, account.data_len() + extra_len, payer, system_program)
+}
+
+/// Create an account owned by the program at the PDA of `seeds`, with the rent paid by `payer`.
+/// Anyone can send lamports to the address beforehand, so it's funded, allocated and assigned
+/// rather than created, the payer only tops up the rent.
+pub fn create_pda_account<'a>(
+    account: &AccountInfo<'a>,
+    space: usize,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    program_id: &Pubkey,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let lamports = Rent::get()?.minimum_balance(space).saturating_sub(account.lamports());
+    if lamports > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, lamports),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(account.key, space as u64),
+        &[account.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(account.key, program_id),
+        &[account.clone(), system_program.clone()],
+        &[seeds],
+    )
+}
+
+/// Close an account owned by the program, its lamports go to `dest`.
+pub fn close_program_account<'a>(account: &AccountInfo<'a>, dest: &AccountInfo<'a>) -> ProgramResult {
+    let lamports = account.lamports();
+    **dest.lamports.borrow_mut() = dest
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ProcessError::StakeAmountOverflow)?;
+    **account.lamports.borrow_mut() = 0;
+    account.realloc(0, false)?;
+    Ok(())
+}
+
+/// Resize the account to `new_len`, new bytes are zeroed.  When it grows, the payer tops up the
+/// lamports for rent-exempt.
+#[inline(never)]
+pub fn resize_account<'a>(
+    account: &AccountInfo<'a>,
+    new_len: usize,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+) -> ProgramResult {
+    account.realloc(new_len, true)?;
     let rent = Rent::get()?;
-    let new_minimum_balance = rent.minimum_balance(old_len + new_data_len);
+    let new_minimum_balance = rent.minimum_balance(new_len);
     let lamports_diff = new_minimum_balance.saturating_sub(account.lamports());
 
     msg!(
@@ -171,7 +661,6 @@ pub fn append_state_to_account<'a, T: BorshSerialize>(
             &[payer.clone(), account.clone(), system_program.clone()],
         )?;
     }
-    borsh::to_writer(&mut account.try_borrow_mut_data()?[old_len..], &state)?;
     Ok(())
 }
 
@@ -215,3 +704,96 @@ pub fn pack_state_to_account<'a, T: BorshSerialize>(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash_pair(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
+        if a <= b {
+            hashv(&[&a, &b]).to_bytes()
+        } else {
+            hashv(&[&b, &a]).to_bytes()
+        }
+    }
+
+    #[test]
+    fn test_verify_merkle_proof() {
+        let keys: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let leaves: Vec<[u8; 32]> = keys.iter().map(|k| hashv(&[k.as_ref()]).to_bytes()).collect();
+        let node01 = hash_pair(leaves[0], leaves[1]);
+        let root = hash_pair(node01, leaves[2]);
+
+        assert!(verify_merkle_proof(&root, &keys[0], &[leaves[1], leaves[2]]));
+        assert!(verify_merkle_proof(&root, &keys[1], &[leaves[0], leaves[2]]));
+        assert!(verify_merkle_proof(&root, &keys[2], &[node01]));
+        assert!(!verify_merkle_proof(&root, &keys[2], &[leaves[0]]));
+        assert!(!verify_merkle_proof(&root, &Pubkey::new_unique(), &[node01]));
+    }
+
+    #[test]
+    fn test_is_direct_payment() {
+        let payer = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let native_mint = Pubkey::from_str(NATIVE_MINT).unwrap();
+        let ata = get_associated_token_address_with_program_id(&payer, &mint, &spl_token::id());
+        let ata_2022 = get_associated_token_address_with_program_id(&payer, &mint, &spl_token_2022::id());
+
+        assert!(is_direct_payment(&payer, &ata, &mint));
+        assert!(is_direct_payment(&payer, &ata_2022, &mint));
+        assert!(!is_direct_payment(&payer, &Pubkey::new_unique(), &mint));
+        assert!(!is_direct_payment(&payer, &payer, &mint));
+        assert!(is_direct_payment(&payer, &payer, &native_mint));
+        assert!(!is_direct_payment(&payer, &Pubkey::new_unique(), &native_mint));
+    }
+
+    #[test]
+    fn test_next_tournament_account() {
+        let program_id = Pubkey::new_unique();
+        let tournament_key = Pubkey::new_unique();
+        let mut tournament_state = TournamentState {
+            is_initialized: true,
+            is_finished: false,
+            title: "MTT".to_string(),
+            owner: Pubkey::new_unique(),
+            token_mint: Pubkey::new_unique(),
+            stake_account: Pubkey::new_unique(),
+            entry_type: Default::default(),
+            start_time: None,
+            registration_close_time: None,
+            late_registration_until: None,
+            games: vec![],
+            final_game: None,
+            players: vec![],
+            prize_pool: 0,
+            ticket_funds: 0,
+        };
+        let mut lamports = 0;
+        let mut data = borsh::to_vec(&tournament_state).unwrap();
+        let tournament_account =
+            AccountInfo::new(&tournament_key, false, true, &mut lamports, &mut data, &program_id, false, 0);
+        let accounts = [tournament_account];
+
+        // Games without tournament take no account
+        let mut game_state = GameState::default();
+        assert!(next_tournament_account(&game_state, &mut accounts.iter()).unwrap().is_none());
+
+        game_state.tournament = Some(tournament_key);
+        let (account, state) = next_tournament_account(&game_state, &mut accounts.iter()).unwrap().unwrap();
+        assert_eq!(account.key, &tournament_key);
+        assert_eq!(state, tournament_state);
+
+        // The account must be the tournament of the game
+        game_state.tournament = Some(Pubkey::new_unique());
+        assert!(next_tournament_account(&game_state, &mut accounts.iter()).is_err());
+
+        // The tournament must be open
+        game_state.tournament = Some(tournament_key);
+        tournament_state.is_finished = true;
+        borsh::to_writer(&mut accounts[0].try_borrow_mut_data().unwrap()[..], &tournament_state).unwrap();
+        assert!(next_tournament_account(&game_state, &mut accounts.iter()).is_err());
+
+        // The account must be given
+        assert!(next_tournament_account(&game_state, &mut [].iter()).is_err());
+    }
+}
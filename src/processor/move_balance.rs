@@ -0,0 +1,330 @@
+//! Move a player with its balance from one game to another, e.g. between the tables of a
+//! multi-table tournament.
+//!
+//! Signed by the transactor of both games, which must be tables of the same tournament, or share
+//! the owner and the token.  The player is removed from the source game, the tokens are
+//! transferred between the two stake accounts, and the player is seated in the destination game
+//! with a pending deposit of the amount received.  The deposit must follow the cash entry rules
+//! of the destination game, and is accepted by its next settlement like any other.  For
+//! tournament games, the tokens stay in the tournament stake account, only the table of the
+//! player is updated and the chips are credited to the balance at once.  Both access versions are bumped, so the
+//! transactor picks up the changes the same way it picks up new joins.
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::ProcessError,
+    events::RaceEvent,
+    state::{players, DepositStatus, GameState, PlayerBalance, PlayerDeposit, PlayerJoin},
+    types::MoveBalanceParams,
+};
+
+use super::misc::{
+    append_state_to_account, is_native_mint, next_mint_account, next_tournament_account,
+    pack_state_to_account, token_transfer, transfer_sol,
+};
+
+#[inline(never)]
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], params: MoveBalanceParams) -> ProgramResult {
+    let account_iter = &mut accounts.iter();
+
+    let transactor_account = next_account_info(account_iter)?;
+
+    let game_account = next_account_info(account_iter)?;
+
+    let players_reg_account = next_account_info(account_iter)?;
+
+    let stake_account = next_account_info(account_iter)?;
+
+    let pda_account = next_account_info(account_iter)?;
+
+    let dest_game_account = next_account_info(account_iter)?;
+
+    let dest_players_reg_account = next_account_info(account_iter)?;
+
+    let dest_stake_account = next_account_info(account_iter)?;
+
+    let _token_program = next_account_info(account_iter)?;
+
+    let system_program = next_account_info(account_iter)?;
+
+    if !transactor_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if game_account.key.eq(dest_game_account.key) {
+        return Err(ProcessError::InvalidAccountPubkey)?;
+    }
+
+    // The player can leave a paused game, but can't be seated in one
+    if !matches!(game_account.data.borrow()[0], 1 | 3) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    match dest_game_account.data.borrow()[0] {
+        1 => (),
+        3 => return Err(ProcessError::GamePaused)?,
+        _ => return Err(ProgramError::UninitializedAccount),
+    }
+    let mut game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
+    let mut dest_game_state = GameState::unpack(&dest_game_account.try_borrow_data()?)?;
+
+    if game_state.transactor_addr.ne(&Some(*transactor_account.key))
+        || dest_game_state.transactor_addr.ne(&Some(*transactor_account.key))
+    {
+        return Err(ProcessError::SignerNotTransactor)?;
+    }
+
+    if game_state.tournament.ne(&dest_game_state.tournament) {
+        return Err(ProcessError::InvalidTournament)?;
+    }
+
+    if game_state.owner.ne(&dest_game_state.owner) {
+        return Err(ProcessError::InvalidOwner)?;
+    }
+
+    if game_state.token_mint.ne(&dest_game_state.token_mint) {
+        return Err(ProcessError::InvalidMint)?;
+    }
+
+    if players_reg_account.key.ne(&game_state.players_reg_account)
+        || dest_players_reg_account.key.ne(&dest_game_state.players_reg_account)
+    {
+        return Err(ProcessError::InvalidPlayersRegAccount)?;
+    }
+
+    if stake_account.key.ne(&game_state.stake_account)
+        || dest_stake_account.key.ne(&dest_game_state.stake_account)
+    {
+        return Err(ProcessError::InvalidStakeAccount)?;
+    }
+
+    let (pda, bump_seed) = Pubkey::find_program_address(&[game_account.key.as_ref()], program_id);
+
+    if pda.ne(pda_account.key) {
+        return Err(ProcessError::InvalidPDA)?;
+    }
+
+    let mint_account = next_mint_account(stake_account, &game_state.token_mint, account_iter)?;
+
+    let mut tournament = next_tournament_account(&game_state, account_iter)?;
+
+    // Remove the player and its balance from the source game
+    let (index, player) = players::get_player_by_id(&players_reg_account.try_borrow_data()?, params.player_id)?
+        .ok_or(ProcessError::PlayerNotInGame)?;
+
+    if game_state
+        .deposits
+        .iter()
+        .any(|d| d.addr.eq(&player.addr) && d.status == DepositStatus::Pending)
+    {
+        msg!("Player {} has a pending deposit", player.addr);
+        return Err(ProcessError::InvalidDeposit)?;
+    }
+
+    let Some(balance_idx) = game_state
+        .balances
+        .iter()
+        .position(|b| b.player_id == params.player_id && b.balance > 0)
+    else {
+        msg!("Player {} holds no balance", player.addr);
+        return Err(ProcessError::InvalidSettleBalance)?;
+    };
+    let amount = game_state.balances.remove(balance_idx).balance;
+
+    players::remove_player_by_index(&mut players_reg_account.try_borrow_mut_data()?, index)?;
+    game_state.access_version += 1;
+    players::set_versions(&mut players_reg_account.try_borrow_mut_data()?, game_state.access_version, game_state.settle_version)?;
+
+    // Move the tokens, the transfer fee of Token-2022 is paid by the player
+    let signer_seeds: &[&[&[u8]]] = &[&[game_account.key.as_ref(), &[bump_seed]]];
+    let received = if let Some((_, tournament_state)) = &mut tournament {
+        let tournament_player = tournament_state
+            .get_player_mut(&player.addr)
+            .ok_or(ProcessError::PlayerNotInGame)?;
+        tournament_player.game_addr = *dest_game_account.key;
+        amount
+    } else if is_native_mint(&game_state.token_mint) {
+        transfer_sol(stake_account.clone(), dest_stake_account.clone(), Some(amount), signer_seeds)?;
+        amount
+    } else {
+        token_transfer(stake_account, dest_stake_account, pda_account, mint_account, amount, signer_seeds)?
+    };
+
+    // Outside tournaments, the moved funds are a deposit into the destination game
+    if tournament.is_none() {
+        dest_game_state.entry_type.validate_cash_deposit(received, 0)?;
+    }
+
+    // Seat the player in the destination game
+    {
+        let data = dest_players_reg_account.try_borrow_data()?;
+
+        if players::is_player_joined(&data, &player.addr)? {
+            msg!("Player {} joined already", player.addr);
+            return Err(ProcessError::JoinedGameAlready)?;
+        }
+
+        if players::get_players_count(&data)? >= dest_game_state.max_players as usize {
+            return Err(ProcessError::GameFullAlready)?;
+        }
+
+        if params.position >= dest_game_state.max_players {
+            return Err(ProcessError::InvalidPosition)?;
+        }
+    }
+
+    let mut position = params.position;
+    if players::is_position_occupied(&dest_players_reg_account.try_borrow_data()?, position)? {
+        position = players::get_available_position(&dest_players_reg_account.try_borrow_data()?, dest_game_state.max_players)?;
+    }
+
+    dest_game_state.access_version += 1;
+
+    let player_join = PlayerJoin {
+        addr: player.addr,
+        position,
+        access_version: dest_game_state.access_version,
+    };
+    let idx = players::add_player(&mut dest_players_reg_account.try_borrow_mut_data()?, &player_join)?;
+    if idx.is_none() {          // account is full, need realloc
+        players::increase_size_set_position_flag(&mut dest_players_reg_account.try_borrow_mut_data()?, player_join.position)?;
+        players::increase_slots_count(&mut dest_players_reg_account.try_borrow_mut_data()?)?;
+        append_state_to_account(&player_join, dest_players_reg_account, transactor_account, system_program)?;
+    }
+
+    if tournament.is_some() {
+        dest_game_state.balances.push(PlayerBalance {
+            player_id: dest_game_state.access_version,
+            balance: received,
+        });
+    } else {
+        dest_game_state.deposits.push(PlayerDeposit {
+            addr: player.addr,
+            amount: received,
+            access_version: dest_game_state.access_version,
+            settle_version: dest_game_state.settle_version,
+            status: DepositStatus::Pending,
+        });
+    }
+
+    players::set_versions(&mut dest_players_reg_account.try_borrow_mut_data()?, dest_game_state.access_version, dest_game_state.settle_version)?;
+
+    pack_state_to_account(game_state, game_account, transactor_account, system_program)?;
+    pack_state_to_account(dest_game_state, dest_game_account, transactor_account, system_program)?;
+
+    if let Some((tournament_account, tournament_state)) = tournament {
+        pack_state_to_account(tournament_state, tournament_account, transactor_account, system_program)?;
+    }
+
+    RaceEvent::PlayerMoved {
+        game: *game_account.key,
+        dest_game: *dest_game_account.key,
+        player: player.addr,
+        position: player_join.position,
+        access_version: player_join.access_version,
+        amount: received,
+    }
+    .emit()?;
+
+    msg!(
+        "Moved player {} with balance {} to game {}",
+        player.addr,
+        received,
+        dest_game_account.key
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::ProcessError,
+        instruction::RaceInstruction,
+        state::{players, DepositStatus, GameState, PlayerBalance, PlayerJoin},
+        testing::{cash_game, players_reg_data, process_instruction, TestAccount},
+        types::MoveBalanceParams,
+    };
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+
+    struct Table {
+        game: TestAccount,
+        reg: TestAccount,
+        stake: TestAccount,
+    }
+
+    fn table(program_id: &Pubkey, mut game_state: GameState, players: &[PlayerJoin], staked: u64) -> Table {
+        let reg = TestAccount::program_account(program_id, players_reg_data(6, players));
+        let stake = TestAccount::wallet(staked);
+        game_state.players_reg_account = reg.key;
+        game_state.stake_account = stake.key;
+        game_state.access_version = players.len() as u64;
+        let game = TestAccount::program_account(program_id, borsh::to_vec(&game_state).unwrap());
+        Table { game, reg, stake }
+    }
+
+    fn move_balance(program_id: &Pubkey, transactor: &mut TestAccount, from: &mut Table, to: &mut Table) -> ProgramResult {
+        let (pda, _) = Pubkey::find_program_address(&[from.game.key.as_ref()], program_id);
+        process_instruction(
+            program_id,
+            &mut [
+                transactor,
+                &mut from.game,
+                &mut from.reg,
+                &mut from.stake,
+                &mut TestAccount::wallet(0).with_key(pda),
+                &mut to.game,
+                &mut to.reg,
+                &mut to.stake,
+                &mut TestAccount::wallet(0),
+                &mut TestAccount::system_program(),
+            ],
+            RaceInstruction::MoveBalance { params: MoveBalanceParams { player_id: 1, position: 0 } },
+        )
+    }
+
+    #[test]
+    fn test_move_balance_as_deposit() {
+        let program_id = Pubkey::new_unique();
+        let alice = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let mut transactor = TestAccount::wallet(1_000_000_000).signer();
+
+        let mut source_state = cash_game(100, 1000);
+        source_state.owner = owner;
+        source_state.transactor_addr = Some(transactor.key);
+        source_state.balances.push(PlayerBalance { player_id: 1, balance: 500 });
+        let mut source = table(&program_id, source_state, &[PlayerJoin { addr: alice, position: 0, access_version: 1 }], 500);
+
+        // The balance exceeds the max deposit of the destination game
+        let mut dest_state = cash_game(100, 400);
+        dest_state.owner = owner;
+        dest_state.transactor_addr = Some(transactor.key);
+        let mut small_table = table(&program_id, dest_state.clone(), &[], 0);
+        let result = move_balance(&program_id, &mut transactor, &mut source, &mut small_table);
+        assert_eq!(result, Err(ProcessError::InvalidPaymentParams.into()));
+
+        dest_state.entry_type = cash_game(100, 1000).entry_type;
+        let mut dest = table(&program_id, dest_state, &[], 0);
+        move_balance(&program_id, &mut transactor, &mut source, &mut dest).unwrap();
+
+        assert_eq!(source.stake.lamports, 0);
+        assert_eq!(dest.stake.lamports, 500);
+        assert!(source.game.game_state().balances.is_empty());
+        assert!(!players::is_player_joined(&source.reg.data, &alice).unwrap());
+
+        let dest_state = dest.game.game_state();
+        assert!(dest_state.balances.is_empty());
+        assert_eq!(dest_state.deposits.len(), 1);
+        assert_eq!(dest_state.deposits[0].addr, alice);
+        assert_eq!(dest_state.deposits[0].amount, 500);
+        assert_eq!(dest_state.deposits[0].status, DepositStatus::Pending);
+        assert!(players::is_player_joined(&dest.reg.data, &alice).unwrap());
+    }
+}
@@ -0,0 +1,55 @@
+//! Pause a game.
+//!
+//! An emergency switch for the game owner. A paused game rejects new joins, deposits, servers and
+//! bonuses, while settles are limited to ejecting players and paying them out.
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::ProcessError,
+    events::RaceEvent,
+    processor::misc::pack_state_to_account,
+    state::{GameState, GameStatus},
+};
+
+#[inline(never)]
+pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_iter = &mut accounts.iter();
+
+    let owner_account = next_account_info(account_iter)?;
+
+    let game_account = next_account_info(account_iter)?;
+
+    let system_program = next_account_info(account_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    match game_account.data.borrow()[0] {
+        1 => (),
+        3 => return Err(ProcessError::GamePaused)?,
+        _ => return Err(ProgramError::UninitializedAccount),
+    }
+    let mut game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
+
+    if game_state.owner.ne(owner_account.key) {
+        return Err(ProcessError::InvalidOwner)?;
+    }
+
+    game_state.game_status = GameStatus::Paused;
+
+    pack_state_to_account(game_state, game_account, owner_account, system_program)?;
+
+    msg!("Paused game {}", game_account.key);
+
+    RaceEvent::GamePaused { game: *game_account.key }.emit()?;
+
+    Ok(())
+}
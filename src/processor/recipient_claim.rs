@@ -4,18 +4,19 @@ use solana_program::{
     entrypoint::ProgramResult,
     msg,
     program_error::ProgramError,
-    program_pack::Pack,
     pubkey::Pubkey,
 };
-use spl_token::state::Account;
 
 use crate::{
     error::ProcessError,
+    events::RaceEvent,
     processor::misc::general_transfer,
     state::{RecipientSlot, RecipientSlotOwner, RecipientState},
 };
 
-use super::misc::{is_native_mint, pack_state_to_account, validate_receiver};
+use super::misc::{
+    is_native_mint, next_mint_account, pack_state_to_account, unpack_token_account, validate_receiver,
+};
 
 fn claim_from_slot(stake_amount: u64, slot: &mut RecipientSlot, owner: &Pubkey) -> u64 {
     let total_weights: u16 = slot.shares.iter().map(|s| s.weights).sum();
@@ -41,7 +42,7 @@ pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
     let payer = next_account_info(accounts_iter)?;
     let recipient_account = next_account_info(accounts_iter)?;
-    let token_program = next_account_info(accounts_iter)?;
+    let _token_program = next_account_info(accounts_iter)?;
     let system_program = next_account_info(accounts_iter)?;
     let mut recipient_state = RecipientState::try_from_slice(&recipient_account.try_borrow_data()?)?;
 
@@ -65,6 +66,8 @@ pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
 
         validate_receiver(payer.key, &slot.token_addr, receiver.key)?;
 
+        let mint_account = next_mint_account(slot_stake_account, &slot.token_addr, accounts_iter)?;
+
         // The total amount for both claimed and unclaimed
         let stake_amount = if is_native_mint(&slot.token_addr) {
             slot_stake_account.lamports()
@@ -72,7 +75,7 @@ pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
             if slot_stake_account.key.ne(&slot.stake_addr) {
                 return Err(ProgramError::InvalidAccountData);
             }
-            let slot_stake_state = Account::unpack(&slot_stake_account.try_borrow_data()?)?;
+            let slot_stake_state = unpack_token_account(slot_stake_account)?;
             if slot_stake_state.mint.ne(&slot.token_addr) {
                 return Err(ProgramError::InvalidAccountData);
             }
@@ -94,8 +97,16 @@ pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
                 Some(total_claim),
                 pda_account,
                 &[&[recipient_account.key.as_ref(), &[slot.id], &[bump_seed]]],
-                token_program,
+                mint_account,
             )?;
+
+            RaceEvent::RecipientClaimed {
+                recipient: *recipient_account.key,
+                slot_id: slot.id,
+                claimer: *payer.key,
+                amount: total_claim,
+            }
+            .emit()?;
         }
     }
 
@@ -0,0 +1,331 @@
+//! Refund an expired satellite ticket.
+//!
+//! A ticket expires once its game can't be joined anymore: the game is paused, cancelled or
+//! closed, its registration is over, or its tournament is finished.  The value held for the ticket is paid
+//! back to the ticket owner from the stake account of the game, or of its tournament, and the
+//! ticket account is closed to its rent payer.  Anyone can ask for the refund, e.g. the game
+//! owner before closing the game.
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    error::ProcessError,
+    events::RaceEvent,
+    state::{GameState, TicketState, TournamentState},
+};
+
+use super::misc::{close_program_account, general_transfer, next_mint_account, pack_state_to_account, validate_receiver};
+
+#[inline(never)]
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let mut account_iter = accounts.iter();
+
+    let payer_account = next_account_info(&mut account_iter)?;
+
+    let ticket_account = next_account_info(&mut account_iter)?;
+
+    let game_account = next_account_info(&mut account_iter)?;
+
+    let stake_account = next_account_info(&mut account_iter)?;
+
+    let pda_account = next_account_info(&mut account_iter)?;
+
+    let receiver_account = next_account_info(&mut account_iter)?;
+
+    let rent_payer_account = next_account_info(&mut account_iter)?;
+
+    let _token_program = next_account_info(&mut account_iter)?;
+
+    let system_program = next_account_info(&mut account_iter)?;
+
+    if !payer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if ticket_account.owner.ne(program_id) || game_account.owner.ne(program_id) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let ticket_state = TicketState::try_from_slice(&ticket_account.try_borrow_data()?)?;
+    let (ticket_pda, _) = TicketState::find_address(program_id, game_account.key, &ticket_state.owner);
+    if !ticket_state.is_initialized || ticket_state.game_addr.ne(game_account.key) || ticket_pda.ne(ticket_account.key) {
+        return Err(ProcessError::InvalidTicket)?;
+    }
+
+    if rent_payer_account.key.ne(&ticket_state.rent_payer) {
+        return Err(ProcessError::InvalidTicket)?;
+    }
+
+    // Closed games keep no state, only those holding no ticket funds can be closed, unless the
+    // funds are held by the tournament
+    let status = game_account.data.borrow()[0];
+    let mut game_state = match status {
+        1 | 3 | 4 => Some(GameState::unpack(&game_account.try_borrow_data()?)?),
+        2 => None,
+        _ => return Err(ProgramError::UninitializedAccount),
+    };
+
+    let now = Clock::get()?.unix_timestamp as u64;
+    let mut is_expired = game_state.as_ref().is_none_or(|g| g.is_ticket_expired(now));
+
+    let mut tournament = None;
+    let (stake_owner, token_mint, expected_stake) = match &ticket_state.tournament {
+        Some(tournament_key) => {
+            let tournament_account = next_account_info(&mut account_iter)?;
+            if tournament_account.key.ne(tournament_key) {
+                return Err(ProcessError::InvalidTournament)?;
+            }
+            if tournament_account.owner.ne(program_id) {
+                return Err(ProgramError::InvalidAccountOwner);
+            }
+            let mut tournament_state = TournamentState::try_from_slice(&tournament_account.try_borrow_data()?)?;
+            is_expired |= tournament_state.is_finished;
+            tournament_state.ticket_funds = tournament_state
+                .ticket_funds
+                .checked_sub(ticket_state.amount)
+                .ok_or(ProcessError::InvalidTicket)?;
+            let accounts = (*tournament_key, tournament_state.token_mint, tournament_state.stake_account);
+            tournament = Some((tournament_account, tournament_state));
+            accounts
+        }
+        None => {
+            let Some(game_state) = game_state.as_mut() else {
+                return Err(ProcessError::InvalidTicket)?;
+            };
+            game_state.ticket_funds = game_state
+                .ticket_funds
+                .checked_sub(ticket_state.amount)
+                .ok_or(ProcessError::InvalidTicket)?;
+            (*game_account.key, game_state.token_mint, game_state.stake_account)
+        }
+    };
+
+    if !is_expired {
+        msg!("Ticket of game {} can still be redeemed by joining", game_account.key);
+        return Err(ProcessError::TicketNotExpired)?;
+    }
+
+    if expected_stake.ne(stake_account.key) {
+        return Err(ProcessError::InvalidStakeAccount)?;
+    }
+
+    let (pda, bump_seed) = Pubkey::find_program_address(&[stake_owner.as_ref()], program_id);
+    if pda.ne(pda_account.key) {
+        return Err(ProcessError::InvalidPDA)?;
+    }
+
+    validate_receiver(&ticket_state.owner, &token_mint, receiver_account.key)?;
+
+    let mint_account = next_mint_account(stake_account, &token_mint, &mut account_iter)?;
+
+    general_transfer(
+        stake_account,
+        receiver_account,
+        &token_mint,
+        Some(ticket_state.amount),
+        pda_account,
+        &[&[stake_owner.as_ref(), &[bump_seed]]],
+        mint_account,
+    )?;
+
+    close_program_account(ticket_account, rent_payer_account)?;
+
+    match (tournament, game_state) {
+        (Some((tournament_account, tournament_state)), _) => {
+            pack_state_to_account(tournament_state, tournament_account, payer_account, system_program)?
+        }
+        (None, Some(game_state)) => pack_state_to_account(game_state, game_account, payer_account, system_program)?,
+        (None, None) => (),
+    }
+
+    msg!("Refunded ticket of game {} to {}", game_account.key, ticket_state.owner);
+
+    RaceEvent::TicketRefunded {
+        game: *game_account.key,
+        player: ticket_state.owner,
+        amount: ticket_state.amount,
+    }
+    .emit()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::ProcessError,
+        instruction::RaceInstruction,
+        state::{GameStatus, TicketState, TournamentState},
+        testing::{process_instruction, ticket_game, tournament_state, TestAccount},
+    };
+    use solana_program::{entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey};
+
+    struct Refund {
+        payer: TestAccount,
+        ticket: TestAccount,
+        game: TestAccount,
+        stake: TestAccount,
+        pda: TestAccount,
+        receiver: TestAccount,
+        rent_payer: TestAccount,
+        token_program: TestAccount,
+        system_program: TestAccount,
+    }
+
+    impl Refund {
+        /// A ticket of `alice` worth 100 into `game`, held by `stake`.
+        fn new(program_id: &Pubkey, game: TestAccount, stake: TestAccount, tournament: Option<Pubkey>) -> Self {
+            let alice = TestAccount::wallet(0);
+            let rent_payer = TestAccount::wallet(0);
+            let (ticket_pda, _) = TicketState::find_address(program_id, &game.key, &alice.key);
+            let ticket_state = TicketState {
+                is_initialized: true,
+                game_addr: game.key,
+                owner: alice.key,
+                source_game_addr: Pubkey::new_unique(),
+                amount: 100,
+                rent_payer: rent_payer.key,
+                tournament,
+            };
+            let ticket = TestAccount::program_account(program_id, borsh::to_vec(&ticket_state).unwrap()).with_key(ticket_pda);
+            Refund {
+                payer: TestAccount::wallet(0).signer(),
+                ticket,
+                game,
+                pda: stake.clone(),
+                stake,
+                receiver: alice,
+                rent_payer,
+                token_program: TestAccount::wallet(0),
+                system_program: TestAccount::system_program(),
+            }
+        }
+
+        fn process(&mut self, program_id: &Pubkey, tournament: Option<&mut TestAccount>) -> ProgramResult {
+            let mut accounts = vec![
+                &mut self.payer,
+                &mut self.ticket,
+                &mut self.game,
+                &mut self.stake,
+                &mut self.pda,
+                &mut self.receiver,
+                &mut self.rent_payer,
+                &mut self.token_program,
+                &mut self.system_program,
+            ];
+            accounts.extend(tournament);
+            process_instruction(program_id, &mut accounts, RaceInstruction::RefundTicket)
+        }
+    }
+
+    #[test]
+    fn test_refund_ticket_of_cancelled_game() {
+        let program_id = Pubkey::new_unique();
+        let game_key = Pubkey::new_unique();
+        let (pda, _) = Pubkey::find_program_address(&[game_key.as_ref()], &program_id);
+        let mut game_state = ticket_game(100, None, None);
+        game_state.stake_account = pda;
+        game_state.ticket_funds = 100;
+        let game = TestAccount::program_account(&program_id, borsh::to_vec(&game_state).unwrap()).with_key(game_key);
+        let mut refund = Refund::new(&program_id, game, TestAccount::wallet(100).with_key(pda), None);
+        let ticket_rent = refund.ticket.lamports;
+
+        // The game can still be joined with the ticket
+        assert_eq!(refund.process(&program_id, None), Err(ProcessError::TicketNotExpired.into()));
+
+        game_state.game_status = GameStatus::Cancelled;
+        refund.game.data = borsh::to_vec(&game_state).unwrap();
+        refund.process(&program_id, None).unwrap();
+
+        assert_eq!(refund.receiver.lamports, 100);
+        assert_eq!(refund.stake.lamports, 0);
+        assert_eq!(refund.rent_payer.lamports, ticket_rent);
+        assert_eq!(refund.ticket.lamports, 0);
+        assert_eq!(refund.game.game_state().ticket_funds, 0);
+    }
+
+    #[test]
+    fn test_refund_ticket_of_tournament_game() {
+        let program_id = Pubkey::new_unique();
+        let tournament_key = Pubkey::new_unique();
+        let (pda, _) = Pubkey::find_program_address(&[tournament_key.as_ref()], &program_id);
+        let mut game_state = ticket_game(100, None, None);
+        game_state.tournament = Some(tournament_key);
+        let game = TestAccount::program_account(&program_id, borsh::to_vec(&game_state).unwrap());
+        let mut tournament_state = tournament_state(&[game.key], &[], 0);
+        tournament_state.stake_account = pda;
+        tournament_state.ticket_funds = 100;
+        let mut tournament =
+            TestAccount::program_account(&program_id, borsh::to_vec(&tournament_state).unwrap()).with_key(tournament_key);
+        let mut refund = Refund::new(&program_id, game, TestAccount::wallet(100).with_key(pda), Some(tournament_key));
+
+        // The tournament account holding the funds is required
+        assert!(refund.process(&program_id, None).is_err());
+        assert_eq!(
+            refund.process(&program_id, Some(&mut tournament)),
+            Err(ProcessError::TicketNotExpired.into())
+        );
+
+        tournament_state.is_finished = true;
+        tournament.data = borsh::to_vec(&tournament_state).unwrap();
+        refund.process(&program_id, Some(&mut tournament)).unwrap();
+
+        assert_eq!(refund.receiver.lamports, 100);
+        assert_eq!(refund.stake.lamports, 0);
+        assert_eq!(tournament.state::<TournamentState>().ticket_funds, 0);
+    }
+
+    #[test]
+    fn test_refund_ticket_failures() {
+        let program_id = Pubkey::new_unique();
+        let game_key = Pubkey::new_unique();
+        let (pda, _) = Pubkey::find_program_address(&[game_key.as_ref()], &program_id);
+        let mut game_state = ticket_game(100, None, None);
+        game_state.stake_account = pda;
+        game_state.ticket_funds = 100;
+        game_state.game_status = GameStatus::Cancelled;
+        let game = TestAccount::program_account(&program_id, borsh::to_vec(&game_state).unwrap()).with_key(game_key);
+        let mut refund = Refund::new(&program_id, game, TestAccount::wallet(100).with_key(pda), None);
+
+        // The rent goes back to whom paid it
+        let rent_payer = refund.rent_payer.clone();
+        refund.rent_payer = TestAccount::wallet(0);
+        assert_eq!(refund.process(&program_id, None), Err(ProcessError::InvalidTicket.into()));
+        refund.rent_payer = rent_payer;
+
+        // The funds are taken from the stake of the game only
+        let stake = refund.stake.clone();
+        refund.stake = TestAccount::wallet(100);
+        assert_eq!(refund.process(&program_id, None), Err(ProcessError::InvalidStakeAccount.into()));
+        refund.stake = stake;
+
+        // The ticket must be the one of the game
+        let ticket_data = refund.ticket.data.clone();
+        let mut ticket_state: TicketState = refund.ticket.state();
+        ticket_state.game_addr = Pubkey::new_unique();
+        refund.ticket.data = borsh::to_vec(&ticket_state).unwrap();
+        assert_eq!(refund.process(&program_id, None), Err(ProcessError::InvalidTicket.into()));
+        refund.ticket.data = ticket_data;
+
+        refund.payer.is_signer = false;
+        assert_eq!(refund.process(&program_id, None), Err(ProgramError::MissingRequiredSignature));
+        refund.payer.is_signer = true;
+
+        refund.process(&program_id, None).unwrap();
+        assert_eq!(refund.receiver.lamports, 100);
+
+        // A ticket is refunded once, its account is emptied
+        assert!(refund.ticket.data.is_empty());
+        assert!(refund.process(&program_id, None).is_err());
+        assert_eq!(refund.receiver.lamports, 100);
+    }
+}
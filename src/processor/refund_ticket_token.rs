@@ -0,0 +1,115 @@
+//! Refund an expired ticket token.
+//!
+//! Once the game can't be joined anymore, see [`GameState::is_ticket_expired`], a ticket token is
+//! burned by its holder for a refund of its value from the stake account.  The fee part paid when
+//! buying the token stays with the recipient.
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::Sysvar,
+};
+use spl_token_2022::instruction::burn;
+
+use crate::{error::ProcessError, events::RaceEvent, state::GameState};
+
+use super::misc::{
+    general_transfer, next_mint_account, pack_state_to_account, unpack_token_account, validate_receiver,
+};
+
+#[inline(never)]
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_iter = &mut accounts.iter();
+
+    let holder_account = next_account_info(account_iter)?;
+
+    let token_account = next_account_info(account_iter)?;
+
+    let game_account = next_account_info(account_iter)?;
+
+    let stake_account = next_account_info(account_iter)?;
+
+    let pda_account = next_account_info(account_iter)?;
+
+    let receiver_account = next_account_info(account_iter)?;
+
+    let ticket_mint_account = next_account_info(account_iter)?;
+
+    let token_program = next_account_info(account_iter)?;
+
+    let system_program = next_account_info(account_iter)?;
+
+    if !holder_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if game_account.owner.ne(program_id) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    if !matches!(game_account.data.borrow()[0], 1 | 3 | 4) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let mut game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
+
+    if game_state.ticket_mint.ne(&Some(*ticket_mint_account.key))
+        || token_program.key.ne(ticket_mint_account.owner)
+        || token_program.key.ne(token_account.owner)
+        || unpack_token_account(token_account)?.mint.ne(ticket_mint_account.key)
+    {
+        return Err(ProcessError::InvalidTicket)?;
+    }
+
+    if !game_state.is_ticket_expired(Clock::get()?.unix_timestamp as u64) {
+        msg!("Ticket tokens of game {} can still be redeemed by joining", game_account.key);
+        return Err(ProcessError::TicketNotExpired)?;
+    }
+
+    if game_state.stake_account.ne(stake_account.key) {
+        return Err(ProcessError::InvalidStakeAccount)?;
+    }
+
+    let (pda, bump_seed) = Pubkey::find_program_address(&[game_account.key.as_ref()], program_id);
+    if pda.ne(pda_account.key) {
+        return Err(ProcessError::InvalidPDA)?;
+    }
+
+    validate_receiver(holder_account.key, &game_state.token_mint, receiver_account.key)?;
+
+    let mint_account = next_mint_account(stake_account, &game_state.token_mint, account_iter)?;
+
+    let value = game_state.redeem_ticket_token()?;
+
+    invoke(
+        &burn(token_program.key, token_account.key, ticket_mint_account.key, holder_account.key, &[], 1)?,
+        &[token_account.clone(), ticket_mint_account.clone(), holder_account.clone(), token_program.clone()],
+    )?;
+
+    general_transfer(
+        stake_account,
+        receiver_account,
+        &game_state.token_mint,
+        Some(value),
+        pda_account,
+        &[&[game_account.key.as_ref(), &[bump_seed]]],
+        mint_account,
+    )?;
+
+    pack_state_to_account(game_state, game_account, holder_account, system_program)?;
+
+    msg!("Refunded a ticket token of game {} worth {}", game_account.key, value);
+
+    RaceEvent::TicketTokenRefunded {
+        game: *game_account.key,
+        holder: *holder_account.key,
+        amount: value,
+    }
+    .emit()?;
+
+    Ok(())
+}
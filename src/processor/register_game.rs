@@ -42,7 +42,7 @@ pub fn process(_programe_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult
     if game_account.data.borrow()[0] != 1 {
         return Err(ProgramError::UninitializedAccount);
     }
-    let game_state = GameState::try_from_slice(&game_account.try_borrow_data()?)?;
+    let game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
 
     if game_state.owner.ne(payer.key) {
         return Err(ProcessError::InvalidOwner)?;
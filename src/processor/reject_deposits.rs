@@ -1,8 +1,8 @@
+use crate::events::RaceEvent;
 use crate::state::DepositStatus;
 use crate::state::players;
 use crate::types::RejectDepositsParams;
 use crate::{error::ProcessError, state::GameState};
-use borsh::BorshDeserialize;
 use solana_program::pubkey::Pubkey;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
@@ -11,7 +11,9 @@ use solana_program::{
     program_error::ProgramError,
 };
 
-use super::misc::{general_transfer, pack_state_to_account, validate_receiver};
+use super::misc::{
+    general_transfer, next_mint_account, next_tournament_account, pack_state_to_account, validate_receiver,
+};
 
 #[inline(never)]
 pub fn process(
@@ -33,7 +35,7 @@ pub fn process(
 
     let pda_account = next_account_info(&mut account_iter)?;
 
-    let token_program = next_account_info(&mut account_iter)?;
+    let _token_program = next_account_info(&mut account_iter)?;
 
     let system_program = next_account_info(&mut account_iter)?;
 
@@ -41,10 +43,28 @@ pub fn process(
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    if game_account.data.borrow()[0] != 1 {
+    // Deposits of paused games can still be refunded
+    if !matches!(game_account.data.borrow()[0], 1 | 3) {
         return Err(ProgramError::UninitializedAccount);
     }
-    let mut game_state = GameState::try_from_slice(&game_account.try_borrow_data()?)?;
+    let mut game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
+
+    let mint_account = next_mint_account(stake_account, &game_state.token_mint, &mut account_iter)?;
+
+    // The deposits of tournament games are refunded from the tournament stake account
+    let mut tournament = next_tournament_account(&game_state, &mut account_iter)?;
+    let (stake_owner, expected_stake) = match &tournament {
+        Some((tournament_account, tournament_state)) => (*tournament_account.key, tournament_state.stake_account),
+        None => (*game_account.key, game_state.stake_account),
+    };
+    if expected_stake.ne(stake_account.key) {
+        return Err(ProcessError::InvalidStakeAccount)?;
+    }
+
+    let (pda, bump_seed) = Pubkey::find_program_address(&[stake_owner.as_ref()], program_id);
+    if pda.ne(pda_account.key) {
+        return Err(ProcessError::InvalidPDA)?;
+    }
 
     for reject_deposit in reject_deposits {
         let Some(deposit) = game_state
@@ -66,25 +86,39 @@ pub fn process(
 
         deposit.status = DepositStatus::Rejected;
 
+        let (deposit_addr, deposit_amount) = (deposit.addr, deposit.amount);
+
         let receiver_account = next_account_info(&mut account_iter)?;
 
-        if validate_receiver(&deposit.addr, &game_state.token_mint, &receiver_account.key).is_ok() {
-            let (_, bump_seed) =
-                Pubkey::find_program_address(&[game_account.key.as_ref()], program_id);
+        // The deposit is owed to the player from now on, even if the refund is left for later
+        if let Some((_, tournament_state)) = &mut tournament {
+            tournament_state.refund(deposit_amount)?;
+        }
 
+        if validate_receiver(&deposit.addr, &game_state.token_mint, &receiver_account.key).is_ok() {
             general_transfer(
                 stake_account,
                 receiver_account,
                 &game_state.token_mint,
                 Some(deposit.amount),
                 pda_account,
-                &[&[game_account.key.as_ref(), &[bump_seed]]],
-                token_program,
+                &[&[stake_owner.as_ref(), &[bump_seed]]],
+                mint_account,
             )?;
 
             deposit.status = DepositStatus::Refunded;
         }
 
+        game_state.remove_ticket_buyin(&deposit_addr, deposit_amount);
+
+        RaceEvent::Rejected {
+            game: *game_account.key,
+            player: deposit_addr,
+            access_version: reject_deposit,
+            amount: deposit_amount,
+        }
+        .emit()?;
+
         // The PlayerJoin with the same access_version should be removed as well
         // So the player can later join again
 
@@ -95,6 +129,11 @@ pub fn process(
 
         if let Some(idx) = player_to_remove {
             players::remove_player_by_index(&mut players_reg_account.try_borrow_mut_data()?, idx)?;
+
+            // So is the entry of the tournament
+            if let Some((_, tournament_state)) = &mut tournament {
+                tournament_state.remove_player(&deposit_addr);
+            }
         }
     }
 
@@ -107,5 +146,9 @@ pub fn process(
         &system_program,
     )?;
 
+    if let Some((tournament_account, tournament_state)) = tournament {
+        pack_state_to_account(tournament_state, tournament_account, transactor_account, system_program)?;
+    }
+
     Ok(())
 }
@@ -0,0 +1,242 @@
+//! Reopen a closed or cancelled game account for a new session.
+//!
+//! The game keeps its address, so registry entries and links stay valid.  Versions continue from
+//! the previous session, thus servers never confuse the two sessions.
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::ProcessError,
+    events::RaceEvent,
+    state::{ClosedGameState, GameState},
+    types::CreateGameAccountParams,
+};
+
+use super::create_game::init_game;
+
+#[inline(never)]
+pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: CreateGameAccountParams,
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let payer = next_account_info(accounts_iter)?;
+
+    let game_account = next_account_info(accounts_iter)?;
+
+    if !payer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if game_account.data_is_empty() {
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    let (owner, access_version, settle_version) = match game_account.data.borrow()[0] {
+        2 => {
+            // Legacy closed accounts have only the status byte, their versions are lost
+            let Ok(closed_state) = ClosedGameState::try_from_slice(&game_account.try_borrow_data()?) else {
+                msg!("The closed game account has no versions recorded");
+                return Err(ProcessError::CantReopenGame)?;
+            };
+            (closed_state.owner, closed_state.access_version, closed_state.settle_version)
+        }
+        4 => {
+            let game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
+            if !game_state.bonuses.is_empty() || game_state.sponsor_funds > 0 {
+                msg!("Bonuses and sponsor funds must be claimed by closing the game before reopening");
+                return Err(ProcessError::CantReopenGame)?;
+            }
+            if !game_state.deposits.is_empty() || game_state.ticket_funds > 0 || game_state.presold_funds > 0 {
+                msg!("Deposits and tickets must be refunded or redeemed before reopening");
+                return Err(ProcessError::OutstandingFunds)?;
+            }
+            (game_state.owner, game_state.access_version, game_state.settle_version)
+        }
+        _ => return Err(ProcessError::CantReopenGame)?,
+    };
+
+    if owner.ne(payer.key) {
+        return Err(ProcessError::InvalidOwner)?;
+    }
+
+    msg!(
+        "Reopen game from access version {}, settle version {}",
+        access_version,
+        settle_version
+    );
+
+    init_game(program_id, accounts, params, access_version + 1, settle_version + 1)?;
+
+    RaceEvent::GameReopened {
+        game: *game_account.key,
+        access_version: access_version + 1,
+        settle_version: settle_version + 1,
+    }
+    .emit()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::ProcessError,
+        instruction::RaceInstruction,
+        state::{players, ClosedGameState, DepositStatus, GameState, GameStatus, PlayerDeposit},
+        testing::{process_instruction, ticket_game, TestAccount},
+        types::CreateGameAccountParams,
+    };
+    use solana_program::{entrypoint::ProgramResult, pubkey::Pubkey};
+
+    struct Reopen {
+        owner: TestAccount,
+        game: TestAccount,
+        players_reg: TestAccount,
+        stake: TestAccount,
+        mint: TestAccount,
+        token_program: TestAccount,
+        bundle: TestAccount,
+        recipient: TestAccount,
+        system_program: TestAccount,
+    }
+
+    impl Reopen {
+        /// The game account of `owner` with `data`, and a fresh players reg account.
+        fn new(program_id: &Pubkey, owner: TestAccount, data: Vec<u8>) -> Self {
+            let game = TestAccount::program_account(program_id, data);
+            let (pda, _) = Pubkey::find_program_address(&[game.key.as_ref()], program_id);
+            Reopen {
+                owner,
+                game,
+                players_reg: TestAccount::program_account(program_id, vec![0; players::HEAD_LEN]),
+                stake: TestAccount::wallet(0).with_key(pda),
+                mint: TestAccount::wallet(0).with_key(spl_token::native_mint::id()),
+                token_program: TestAccount::wallet(0),
+                bundle: TestAccount::wallet(0),
+                recipient: TestAccount::program_account(program_id, vec![1]),
+                system_program: TestAccount::system_program(),
+            }
+        }
+
+        fn process(&mut self, program_id: &Pubkey) -> ProgramResult {
+            let params = CreateGameAccountParams {
+                title: "Reopened".to_string(),
+                max_players: 6,
+                entry_type: ticket_game(100, None, None).entry_type,
+                data: vec![],
+                start_time: None,
+                registration_close_time: None,
+                late_registration_until: None,
+                invite_key: None,
+                gatekeeper: None,
+            };
+            process_instruction(
+                program_id,
+                &mut [
+                    &mut self.owner,
+                    &mut self.game,
+                    &mut self.players_reg,
+                    &mut self.stake,
+                    &mut self.mint,
+                    &mut self.token_program,
+                    &mut self.bundle,
+                    &mut self.recipient,
+                    &mut self.system_program,
+                ],
+                RaceInstruction::ReopenGame { params },
+            )
+        }
+    }
+
+    #[test]
+    fn test_reopen_closed_game() {
+        let program_id = Pubkey::new_unique();
+        let owner = TestAccount::wallet(1_000_000_000).signer();
+        let closed_state = ClosedGameState {
+            game_status: GameStatus::Closed,
+            owner: owner.key,
+            access_version: 7,
+            settle_version: 3,
+        };
+        let mut reopen = Reopen::new(&program_id, owner, borsh::to_vec(&closed_state).unwrap());
+
+        reopen.process(&program_id).unwrap();
+
+        // The versions continue from the last session, which is not started yet
+        let game_state = reopen.game.game_state();
+        assert_eq!(game_state.game_status, GameStatus::Initialized);
+        assert_eq!(game_state.owner, reopen.owner.key);
+        assert_eq!(game_state.title, "Reopened");
+        assert_eq!(game_state.access_version, 8);
+        assert_eq!(game_state.settle_version, 4);
+        assert_eq!(game_state.start_settle_version, 4);
+        assert!(!game_state.is_started());
+        assert_eq!(game_state.players_reg_account, reopen.players_reg.key);
+        assert_eq!(players::get_layout_version(&reopen.players_reg.data).unwrap(), players::LAYOUT_VERSION);
+        assert_eq!(players::get_players_count(&reopen.players_reg.data).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_reopen_cancelled_game() {
+        let program_id = Pubkey::new_unique();
+        let owner = TestAccount::wallet(1_000_000_000).signer();
+        let mut game_state = ticket_game(100, None, None);
+        game_state.game_status = GameStatus::Cancelled;
+        game_state.owner = owner.key;
+        game_state.access_version = 2;
+        game_state.deposits = vec![PlayerDeposit {
+            addr: Pubkey::new_unique(),
+            amount: 100,
+            access_version: 1,
+            settle_version: 0,
+            status: DepositStatus::Rejected,
+        }];
+        let mut reopen = Reopen::new(&program_id, owner, borsh::to_vec(&game_state).unwrap());
+
+        // The deposits left by the cancellation must be refunded first
+        assert_eq!(reopen.process(&program_id), Err(ProcessError::OutstandingFunds.into()));
+
+        game_state.deposits.clear();
+        reopen.game.data = borsh::to_vec(&game_state).unwrap();
+        reopen.process(&program_id).unwrap();
+
+        let game_state = reopen.game.game_state();
+        assert_eq!(game_state.game_status, GameStatus::Initialized);
+        assert_eq!(game_state.access_version, 3);
+        assert_eq!(game_state.settle_version, 1);
+    }
+
+    #[test]
+    fn test_reopen_game_failures() {
+        let program_id = Pubkey::new_unique();
+        let owner = TestAccount::wallet(1_000_000_000).signer();
+        let closed_state = ClosedGameState {
+            game_status: GameStatus::Closed,
+            owner: Pubkey::new_unique(),
+            access_version: 7,
+            settle_version: 3,
+        };
+
+        // Only the owner of the last session can reopen
+        let mut reopen = Reopen::new(&program_id, owner.clone(), borsh::to_vec(&closed_state).unwrap());
+        assert_eq!(reopen.process(&program_id), Err(ProcessError::InvalidOwner.into()));
+
+        // Legacy closed accounts have no versions to continue from
+        let mut reopen = Reopen::new(&program_id, owner.clone(), vec![GameStatus::Closed as u8]);
+        assert_eq!(reopen.process(&program_id), Err(ProcessError::CantReopenGame.into()));
+
+        // Live games can't be reopened
+        let game_state = GameState { owner: owner.key, ..ticket_game(100, None, None) };
+        let mut reopen = Reopen::new(&program_id, owner, borsh::to_vec(&game_state).unwrap());
+        assert_eq!(reopen.process(&program_id), Err(ProcessError::CantReopenGame.into()));
+        assert_eq!(reopen.game.game_state().game_status, GameStatus::Initialized);
+    }
+}
@@ -0,0 +1,52 @@
+//! Resume a paused game.
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::ProcessError,
+    events::RaceEvent,
+    processor::misc::pack_state_to_account,
+    state::{GameState, GameStatus},
+};
+
+#[inline(never)]
+pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_iter = &mut accounts.iter();
+
+    let owner_account = next_account_info(account_iter)?;
+
+    let game_account = next_account_info(account_iter)?;
+
+    let system_program = next_account_info(account_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    match game_account.data.borrow()[0] {
+        3 => (),
+        1 => return Err(ProcessError::GameNotPaused)?,
+        _ => return Err(ProgramError::UninitializedAccount),
+    }
+    let mut game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
+
+    if game_state.owner.ne(owner_account.key) {
+        return Err(ProcessError::InvalidOwner)?;
+    }
+
+    game_state.game_status = GameStatus::Initialized;
+
+    pack_state_to_account(game_state, game_account, owner_account, system_program)?;
+
+    msg!("Resumed game {}", game_account.key);
+
+    RaceEvent::GameResumed { game: *game_account.key }.emit()?;
+
+    Ok(())
+}
@@ -12,7 +12,7 @@ use crate::{
     error::ProcessError, processor::misc::pack_state_to_account,
     state::{players, GameState, ServerJoin, ServerState}
 };
-use crate::{constants::MAX_SERVER_NUM, types::ServeParams};
+use crate::{constants::MAX_SERVER_NUM, events::RaceEvent, types::ServeParams};
 
 #[inline(never)]
 pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], params: ServeParams) -> ProgramResult {
@@ -31,10 +31,12 @@ pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], params: ServePara
 
     let players_reg_account = next_account_info(account_iter)?;
 
-    if game_account.data.borrow()[0] != 1 {
-        return Err(ProgramError::UninitializedAccount);
+    match game_account.data.borrow()[0] {
+        1 => (),
+        3 => return Err(ProcessError::GamePaused)?,
+        _ => return Err(ProgramError::UninitializedAccount),
     }
-    let mut game_state = GameState::try_from_slice(&game_account.try_borrow_data()?)?;
+    let mut game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
 
     if players_reg_account.key.ne(&game_state.players_reg_account) {
         return Err(ProcessError::InvalidPlayersRegAccount)?;
@@ -90,5 +92,12 @@ pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], params: ServePara
 
     pack_state_to_account(game_state, &game_account, &payer_account, &system_program)?;
 
+    RaceEvent::ServerJoined {
+        game: *game_account.key,
+        server: *payer_account.key,
+        access_version: new_access_version,
+    }
+    .emit()?;
+
     Ok(())
 }
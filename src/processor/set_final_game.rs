@@ -0,0 +1,76 @@
+//! Set the final table of a tournament.
+//!
+//! Signed by the tournament owner.  Only the transactor of the final table can pay the prizes, so
+//! the owner picks it among the linked games, usually once the other tables are broken.  It can
+//! be changed until the tournament is settled.
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::ProcessError,
+    events::RaceEvent,
+    state::{GameState, TournamentState},
+};
+
+use super::misc::pack_state_to_account;
+
+#[inline(never)]
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_iter = &mut accounts.iter();
+
+    let owner_account = next_account_info(account_iter)?;
+
+    let tournament_account = next_account_info(account_iter)?;
+
+    let game_account = next_account_info(account_iter)?;
+
+    let system_program = next_account_info(account_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if tournament_account.owner.ne(program_id) || game_account.owner.ne(program_id) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let mut tournament_state = TournamentState::try_from_slice(&tournament_account.try_borrow_data()?)?;
+    if !tournament_state.is_open() {
+        return Err(ProcessError::InvalidTournament)?;
+    }
+
+    if tournament_state.owner.ne(owner_account.key) {
+        return Err(ProcessError::InvalidOwner)?;
+    }
+
+    if !matches!(game_account.data.borrow()[0], 1 | 3) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
+
+    if !tournament_state.games.contains(game_account.key) || game_state.tournament.ne(&Some(*tournament_account.key)) {
+        msg!("Game {} is not linked to the tournament", game_account.key);
+        return Err(ProcessError::InvalidTournament)?;
+    }
+
+    tournament_state.final_game = Some(*game_account.key);
+
+    pack_state_to_account(tournament_state, tournament_account, owner_account, system_program)?;
+
+    msg!("Set game {} as the final table of tournament {}", game_account.key, tournament_account.key);
+
+    RaceEvent::FinalGameSet {
+        tournament: *tournament_account.key,
+        game: *game_account.key,
+    }
+    .emit()?;
+
+    Ok(())
+}
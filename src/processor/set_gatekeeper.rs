@@ -0,0 +1,59 @@
+//! Set or remove the gatekeeper of a game, who approves every join.
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::ProcessError,
+    events::RaceEvent,
+    processor::misc::pack_state_to_account,
+    state::GameState,
+    types::SetGatekeeperParams,
+};
+
+#[inline(never)]
+pub fn process(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: SetGatekeeperParams,
+) -> ProgramResult {
+    let account_iter = &mut accounts.iter();
+
+    let owner_account = next_account_info(account_iter)?;
+
+    let game_account = next_account_info(account_iter)?;
+
+    let system_program = next_account_info(account_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if !matches!(game_account.data.borrow()[0], 1 | 3) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let mut game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
+
+    if game_state.owner.ne(owner_account.key) {
+        return Err(ProcessError::InvalidOwner)?;
+    }
+
+    game_state.gatekeeper = params.gatekeeper;
+
+    pack_state_to_account(game_state, game_account, owner_account, system_program)?;
+
+    msg!("Set gatekeeper of game {} to {:?}", game_account.key, params.gatekeeper);
+
+    RaceEvent::GatekeeperSet {
+        game: *game_account.key,
+        gatekeeper: params.gatekeeper,
+    }
+    .emit()?;
+
+    Ok(())
+}
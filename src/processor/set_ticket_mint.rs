@@ -0,0 +1,117 @@
+//! Set the mint of ticket tokens for a ticket game.
+//!
+//! Signed by the game owner, who is the mint authority of a fresh mint of either token program,
+//! with no decimals and no freeze authority.  The mint authority is transferred to the game PDA, so tickets can
+//! only be minted by `BuyTicket`, each paid with the ticket amount.
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+    program_option::COption,
+    pubkey::Pubkey,
+};
+use spl_token_2022::instruction::{set_authority, AuthorityType};
+
+use crate::{
+    error::ProcessError,
+    events::RaceEvent,
+    state::{EntryType, GameState},
+};
+
+use super::{
+    create_game::validate_mint_extensions,
+    misc::{is_token_program, pack_state_to_account, unpack_mint},
+};
+
+#[inline(never)]
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_iter = &mut accounts.iter();
+
+    let owner_account = next_account_info(account_iter)?;
+
+    let game_account = next_account_info(account_iter)?;
+
+    let ticket_mint_account = next_account_info(account_iter)?;
+
+    let token_program = next_account_info(account_iter)?;
+
+    let system_program = next_account_info(account_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    match game_account.data.borrow()[0] {
+        1 => (),
+        3 => return Err(ProcessError::GamePaused)?,
+        _ => return Err(ProgramError::UninitializedAccount),
+    }
+    let mut game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
+
+    if game_state.owner.ne(owner_account.key) {
+        return Err(ProcessError::InvalidOwner)?;
+    }
+
+    if !matches!(game_state.entry_type.payment_type(), EntryType::Ticket { .. }) {
+        return Err(ProcessError::InvalidEntryType)?;
+    }
+
+    // The entries of tournament games are held by the tournament
+    if game_state.tournament.is_some() {
+        return Err(ProcessError::InvalidTournament)?;
+    }
+
+    if game_state.ticket_mint.is_some() {
+        msg!("Ticket mint is set already: {:?}", game_state.ticket_mint);
+        return Err(ProcessError::InvalidTicket)?;
+    }
+
+    if !is_token_program(token_program.key) || ticket_mint_account.owner.ne(token_program.key) {
+        return Err(ProcessError::InvalidTokenMint)?;
+    }
+
+    validate_mint_extensions(ticket_mint_account)?;
+
+    let mint_state = unpack_mint(ticket_mint_account)?;
+    if mint_state.decimals != 0
+        || mint_state.supply != 0
+        || mint_state.freeze_authority.is_some()
+        || mint_state.mint_authority != COption::Some(*owner_account.key)
+    {
+        msg!("Ticket mint must be fresh, with no decimals and no freeze authority");
+        return Err(ProcessError::InvalidTokenMint)?;
+    }
+
+    let (pda, _bump_seed) = Pubkey::find_program_address(&[game_account.key.as_ref()], program_id);
+
+    let set_authority_ix = set_authority(
+        token_program.key,
+        ticket_mint_account.key,
+        Some(&pda),
+        AuthorityType::MintTokens,
+        owner_account.key,
+        &[owner_account.key],
+    )?;
+
+    invoke(
+        &set_authority_ix,
+        &[ticket_mint_account.clone(), owner_account.clone(), token_program.clone()],
+    )?;
+
+    game_state.ticket_mint = Some(*ticket_mint_account.key);
+
+    pack_state_to_account(game_state, game_account, owner_account, system_program)?;
+
+    msg!("Set ticket mint {} for game {}", ticket_mint_account.key, game_account.key);
+
+    RaceEvent::TicketMintSet {
+        game: *game_account.key,
+        ticket_mint: *ticket_mint_account.key,
+    }
+    .emit()?;
+
+    Ok(())
+}
@@ -7,27 +7,29 @@
 //! 1. All changes are sum up to zero.
 //! 2. Player without assets must be ejected.
 
+use crate::constants::TICKET_SEED;
+use crate::events::{PlayerDelta, RaceEvent};
 use crate::state::players;
-use crate::state::{DepositStatus, RecipientState};
-use crate::types::{Award, BalanceChange, Settle, SettleParams, Transfer};
+use crate::state::{DepositStatus, EntryType, GameStatus, RecipientState, TicketState};
+use crate::types::{Award, BalanceChange, Settle, SettleParams, TicketAward, Transfer};
 use crate::{
     error::ProcessError,
     state::{GameState, PlayerBalance},
 };
 use borsh::BorshDeserialize;
-use solana_program::program::invoke_signed;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
     msg,
     program_error::ProgramError,
-    program_pack::Pack,
     pubkey::Pubkey,
 };
-use spl_token::instruction::close_account;
-use spl_token::state::Account;
 
-use super::misc::{general_transfer, is_native_mint, pack_state_to_account, validate_receiver};
+use super::misc::{
+    close_token_account, create_pda_account, general_transfer, is_native_mint,
+    next_mint_account, next_tournament_account, pack_state_to_account, token_transfer,
+    transfer_sol, unpack_token_account, validate_receiver,
+};
 
 #[inline(never)]
 pub fn process(
@@ -45,6 +47,7 @@ pub fn process(
         next_settle_version,
         entry_lock,
         accept_deposits,
+        ticket_awards,
         ..
     } = params;
 
@@ -62,7 +65,7 @@ pub fn process(
 
     let recipient_account = next_account_info(&mut account_iter)?;
 
-    let token_program = next_account_info(&mut account_iter)?;
+    let _token_program = next_account_info(&mut account_iter)?;
 
     let system_program = next_account_info(&mut account_iter)?;
 
@@ -70,13 +73,22 @@ pub fn process(
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    if game_account.data.borrow()[0] != 1 {
+    // Paused games can still be settled, refunded and closed
+    if !matches!(game_account.data.borrow()[0], 1 | 3) {
         return Err(ProgramError::UninitializedAccount);
     }
-    let mut game_state = GameState::try_from_slice(&game_account.try_borrow_data()?)?;
+    let mut game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
 
     msg!("Game state deserialized");
 
+    if game_state.game_status == GameStatus::Paused {
+        validate_paused_settle(&settles, &transfer, &awards, &accept_deposits, &ticket_awards)?;
+    }
+
+    if game_state.tournament.is_some() {
+        validate_tournament_settle(&settles, &transfer, &ticket_awards)?;
+    }
+
     if game_state.settle_version != settle_version {
         return Err(ProcessError::InvalidSettleVersion)?;
     }
@@ -98,6 +110,22 @@ pub fn process(
         return Err(ProcessError::InvalidPDA)?;
     }
 
+    let mint_account = next_mint_account(stake_account, &game_state.token_mint, &mut account_iter)?;
+
+    let deltas: Vec<PlayerDelta> = settles
+        .iter()
+        .map(|s| PlayerDelta {
+            player_id: s.player_id,
+            change: match s.change {
+                Some(BalanceChange::Add(amount)) => amount as i128,
+                Some(BalanceChange::Sub(amount)) => -(amount as i128),
+                None => 0,
+            },
+            amount: s.amount,
+            eject: s.eject,
+        })
+        .collect();
+
     // msg!("Handle settles: {:?}", settles);
 
     handle_settles(
@@ -108,7 +136,7 @@ pub fn process(
         stake_account,
         pda_account,
         bump_seed,
-        token_program,
+        mint_account,
         &mut account_iter,
     )?;
 
@@ -123,7 +151,7 @@ pub fn process(
             recipient_account,
             pda_account,
             bump_seed,
-            token_program,
+            mint_account,
             &mut account_iter,
         )?;
     }
@@ -137,7 +165,21 @@ pub fn process(
         pda_account,
         transactor_account,
         bump_seed,
-        token_program,
+        &mut account_iter,
+    )?;
+
+    handle_ticket_awards(
+        &game_state,
+        *ticket_awards,
+        program_id,
+        game_account,
+        players_reg_account,
+        stake_account,
+        pda_account,
+        transactor_account,
+        system_program,
+        bump_seed,
+        mint_account,
         &mut account_iter,
     )?;
 
@@ -157,7 +199,7 @@ pub fn process(
         .deposits
         .retain(|d| matches!(d.status, DepositStatus::Pending | DepositStatus::Rejected));
 
-    validate_balance(&game_state, &stake_account)?;
+    validate_balance(&mut game_state, &stake_account)?;
     // msg!("Balance validation passed");
 
     // msg!("Bump settle version to {}", next_settle_version);
@@ -177,33 +219,95 @@ pub fn process(
         &system_program,
     )?;
 
+    RaceEvent::Settled {
+        game: *game_account.key,
+        settle_version: next_settle_version,
+        deltas,
+    }
+    .emit()?;
+
+    Ok(())
+}
+
+/// A paused game can only eject players and pay them out.
+#[inline(never)]
+fn validate_paused_settle(
+    settles: &[Settle],
+    transfer: &Option<Transfer>,
+    awards: &[Award],
+    accept_deposits: &[u64],
+    ticket_awards: &[TicketAward],
+) -> ProgramResult {
+    if transfer.is_some() || !awards.is_empty() || !accept_deposits.is_empty() || !ticket_awards.is_empty() {
+        msg!("Transfers, awards and deposit acceptance are not allowed when game is paused");
+        return Err(ProcessError::GamePaused)?;
+    }
+
+    for settle in settles.iter() {
+        if !settle.eject || matches!(settle.change, Some(BalanceChange::Add(_))) {
+            msg!("Settle for player {} must eject without adding balance", settle.player_id);
+            return Err(ProcessError::GamePaused)?;
+        }
+    }
+
     Ok(())
 }
 
+/// The entries of tournament games are held by the tournament, and the prizes are paid with
+/// `SettleTournament`.  Balances are chips, so nothing is paid out of the game's own stake.
+#[inline(never)]
+fn validate_tournament_settle(
+    settles: &[Settle],
+    transfer: &Option<Transfer>,
+    ticket_awards: &[TicketAward],
+) -> ProgramResult {
+    if transfer.is_some() || !ticket_awards.is_empty() || settles.iter().any(|s| s.amount > 0) {
+        msg!("Payouts are not allowed in settles of tournament games");
+        return Err(ProcessError::InvalidTournament)?;
+    }
+    Ok(())
+}
+
+/// Validate the stake amount against players' balances and unhandled deposits.  The rest must be
+/// sponsor funds, which can be drawn by settles but never increased.
 #[inline(never)]
 fn validate_balance<'a, 'b>(
-    game_state: &'a GameState,
+    game_state: &'a mut GameState,
     stake_account: &'a AccountInfo<'b>,
 ) -> ProgramResult {
     let stake_amount = if is_native_mint(&game_state.token_mint) {
         stake_account.lamports()
     } else {
-        let token_state = Account::unpack(&stake_account.try_borrow_data()?)?;
+        let token_state = unpack_token_account(stake_account)?;
         token_state.amount
     };
 
-    let balance_sum = game_state.balances.iter().map(|b| b.balance).sum::<u64>();
-    let unhandled_deposit = game_state
-        .deposits
-        .iter()
-        .filter(|d| matches!(d.status, DepositStatus::Pending | DepositStatus::Rejected))
-        .map(|d| d.amount)
-        .sum::<u64>();
+    // For tournament games, balances, deposits and tickets are held by the tournament stake account
+    let (balance_sum, unhandled_deposit, ticket_funds) = if game_state.tournament.is_some() {
+        (0, 0, 0)
+    } else {
+        let balance_sum = game_state.balances.iter().map(|b| b.balance).sum::<u64>();
+        let unhandled_deposit = game_state
+            .deposits
+            .iter()
+            .filter(|d| matches!(d.status, DepositStatus::Pending | DepositStatus::Rejected))
+            .map(|d| d.amount)
+            .sum::<u64>();
+        (balance_sum, unhandled_deposit, game_state.ticket_funds + game_state.presold_funds)
+    };
 
-    if !(stake_amount == balance_sum + unhandled_deposit) {
-        msg!("Stake amount = {}, balance_sum + unhandled_deposit = {}", stake_amount, balance_sum + unhandled_deposit);
+    let sponsor_funds = stake_amount.checked_sub(balance_sum + unhandled_deposit + ticket_funds);
+
+    if !sponsor_funds.is_some_and(|f| f <= game_state.sponsor_funds) {
+        msg!(
+            "Stake amount = {}, balance_sum + unhandled_deposit = {}, sponsor funds = {}",
+            stake_amount,
+            balance_sum + unhandled_deposit,
+            game_state.sponsor_funds
+        );
         Err(ProcessError::UnbalancedGameStake)?
     }
+    game_state.sponsor_funds = sponsor_funds.unwrap_or_default();
     Ok(())
 }
 
@@ -216,7 +320,7 @@ fn handle_settles<'a, 'b, 'c, I: Iterator<Item = &'a AccountInfo<'b>>>(
     stake_account: &'a AccountInfo<'b>,
     pda_account: &'a AccountInfo<'b>,
     bump_seed: u8,
-    token_program: &'a AccountInfo<'b>,
+    mint_account: Option<&'a AccountInfo<'b>>,
     account_iter: &'c mut I,
 ) -> ProgramResult {
     let mut pays = vec![];
@@ -279,7 +383,7 @@ fn handle_settles<'a, 'b, 'c, I: Iterator<Item = &'a AccountInfo<'b>>>(
             Some(amount),
             pda_account,
             &[&[game_account.key.as_ref(), &[bump_seed]]],
-            token_program,
+            mint_account,
         )?;
     }
 
@@ -295,7 +399,6 @@ fn handle_bonuses<'a, 'b, 'c, I: Iterator<Item = &'a AccountInfo<'b>>>(
     pda_account: &'a AccountInfo<'b>,
     transactor_account: &'a AccountInfo<'b>,
     bump_seed: u8,
-    token_program: &'a AccountInfo<'b>,
     account_iter: &'c mut I,
 ) -> ProgramResult {
     for Award {
@@ -310,6 +413,7 @@ fn handle_bonuses<'a, 'b, 'c, I: Iterator<Item = &'a AccountInfo<'b>>>(
 
             let bonus_account = next_account_info(account_iter)?;
             let receiver_account = next_account_info(account_iter)?;
+            let bonus_mint_account = next_mint_account(bonus_account, &bonus.token_addr, account_iter)?;
 
             if bonus.stake_addr.ne(&bonus_account.key) {
                 return Err(ProcessError::InvalidAwardIdentifier)?;
@@ -330,31 +434,157 @@ fn handle_bonuses<'a, 'b, 'c, I: Iterator<Item = &'a AccountInfo<'b>>>(
                 None,
                 pda_account,
                 &[&[game_account.key.as_ref(), &[bump_seed]]],
-                token_program,
-            )?;
-
-            let close_ix = close_account(
-                token_program.key,
-                bonus_account.key,
-                transactor_account.key,
-                pda_account.key,
-                &[pda_account.key],
+                bonus_mint_account,
             )?;
 
-            invoke_signed(
-                &close_ix,
-                &[
-                    bonus_account.clone(),
-                    transactor_account.clone(),
-                    pda_account.clone(),
-                ],
+            close_token_account(
+                bonus_account,
+                transactor_account,
+                pda_account,
                 &[&[game_account.key.as_ref(), &[bump_seed]]],
             )?;
+
+            RaceEvent::BonusAwarded {
+                game: *game_account.key,
+                player: player.addr,
+                identifier: bonus.identifier.clone(),
+                token: bonus.token_addr,
+                amount: bonus.amount,
+            }
+            .emit()?;
+        }
+    }
+    Ok(())
+}
+
+/// Award tickets into other games.  For each ticket, the ticket amount is moved from this game's
+/// stake to the target game's stake, or its tournament's, and a ticket account is created for the
+/// player to join the target game with.
+#[inline(never)]
+fn handle_ticket_awards<'a, 'b, 'c, I: Iterator<Item = &'a AccountInfo<'b>>>(
+    game_state: &'c GameState,
+    ticket_awards: Vec<TicketAward>,
+    program_id: &Pubkey,
+    game_account: &'a AccountInfo<'b>,
+    players_reg_account: &'a AccountInfo<'b>,
+    stake_account: &'a AccountInfo<'b>,
+    pda_account: &'a AccountInfo<'b>,
+    transactor_account: &'a AccountInfo<'b>,
+    system_program: &'a AccountInfo<'b>,
+    bump_seed: u8,
+    mint_account: Option<&'a AccountInfo<'b>>,
+    account_iter: &'c mut I,
+) -> ProgramResult {
+    for TicketAward { player_id, game_addr } in ticket_awards {
+        let target_game_account = next_account_info(account_iter)?;
+        let target_stake_account = next_account_info(account_iter)?;
+        let ticket_account = next_account_info(account_iter)?;
+
+        if target_game_account.key.ne(&game_addr) || game_addr.eq(game_account.key) {
+            return Err(ProcessError::InvalidAccountPubkey)?;
         }
+
+        if target_game_account.owner.ne(program_id) {
+            return Err(ProgramError::InvalidAccountOwner);
+        }
+
+        if !matches!(target_game_account.data.borrow()[0], 1 | 3) {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        let mut target_state = GameState::unpack(&target_game_account.try_borrow_data()?)?;
+
+        let amount = ticket_amount(game_state, &target_state)?;
+
+        // The entries of tournament games are held by the tournament, so are the ticket funds
+        let mut tournament = next_tournament_account(&target_state, account_iter)?;
+        let target_stake = match &tournament {
+            Some((_, tournament_state)) => tournament_state.stake_account,
+            None => target_state.stake_account,
+        };
+        if target_stake_account.key.ne(&target_stake) {
+            return Err(ProcessError::InvalidStakeAccount)?;
+        }
+
+        let player = match players::get_player_by_id(&players_reg_account.try_borrow_data()?, player_id)? {
+            Some((_, p)) => p,
+            None => return Err(ProcessError::InvalidAwardPlayerId)?,
+        };
+
+        let (ticket_pda, ticket_bump) = TicketState::find_address(program_id, &game_addr, &player.addr);
+        // One unredeemed ticket for each game and player
+        if ticket_pda.ne(ticket_account.key) || !ticket_account.data_is_empty() {
+            return Err(ProcessError::InvalidTicket)?;
+        }
+
+        let signer_seeds: &[&[&[u8]]] = &[&[game_account.key.as_ref(), &[bump_seed]]];
+        let received = if is_native_mint(&game_state.token_mint) {
+            transfer_sol(stake_account.clone(), target_stake_account.clone(), Some(amount), signer_seeds)?;
+            amount
+        } else {
+            token_transfer(stake_account, target_stake_account, pda_account, mint_account, amount, signer_seeds)?
+        };
+
+        let ticket_funds = match &mut tournament {
+            Some((_, tournament_state)) => &mut tournament_state.ticket_funds,
+            None => &mut target_state.ticket_funds,
+        };
+        *ticket_funds = ticket_funds
+            .checked_add(received)
+            .ok_or(ProcessError::StakeAmountOverflow)?;
+
+        let ticket_state = TicketState {
+            is_initialized: true,
+            game_addr,
+            owner: player.addr,
+            source_game_addr: *game_account.key,
+            amount: received,
+            rent_payer: *transactor_account.key,
+            tournament: target_state.tournament,
+        };
+        create_pda_account(
+            ticket_account,
+            borsh::object_length(&ticket_state)?,
+            transactor_account,
+            system_program,
+            program_id,
+            &[TICKET_SEED.as_bytes(), game_addr.as_ref(), player.addr.as_ref(), &[ticket_bump]],
+        )?;
+        borsh::to_writer(&mut ticket_account.try_borrow_mut_data()?[..], &ticket_state)?;
+
+        match tournament {
+            Some((tournament_account, tournament_state)) => {
+                pack_state_to_account(tournament_state, tournament_account, transactor_account, system_program)?
+            }
+            None => pack_state_to_account(target_state, target_game_account, transactor_account, system_program)?,
+        }
+
+        msg!("Awarded ticket of game {} to {}", game_addr, player.addr);
+
+        RaceEvent::TicketAwarded {
+            game: *game_account.key,
+            player: player.addr,
+            target_game: game_addr,
+            amount: received,
+        }
+        .emit()?;
     }
     Ok(())
 }
 
+/// The ticket amount of the target game of a ticket award, which must be a ticket game of the
+/// same token.
+fn ticket_amount(game_state: &GameState, target_state: &GameState) -> Result<u64, ProgramError> {
+    let EntryType::Ticket { amount, .. } = target_state.entry_type.payment_type() else {
+        return Err(ProcessError::InvalidEntryType)?;
+    };
+
+    if target_state.token_mint.ne(&game_state.token_mint) {
+        return Err(ProcessError::InvalidMint)?;
+    }
+
+    Ok(*amount)
+}
+
 #[inline(never)]
 fn handle_transfer<'a, 'b, 'c, I: Iterator<Item = &'a AccountInfo<'b>>>(
     game_state: &'c GameState,
@@ -364,7 +594,7 @@ fn handle_transfer<'a, 'b, 'c, I: Iterator<Item = &'a AccountInfo<'b>>>(
     recipient_account: &'a AccountInfo<'b>,
     pda_account: &'a AccountInfo<'b>,
     bump_seed: u8,
-    token_program: &'a AccountInfo<'b>,
+    mint_account: Option<&'a AccountInfo<'b>>,
     account_iter: &'c mut I,
 ) -> ProgramResult {
     let recipient_state = RecipientState::try_from_slice(&recipient_account.try_borrow_data()?)?;
@@ -384,7 +614,7 @@ fn handle_transfer<'a, 'b, 'c, I: Iterator<Item = &'a AccountInfo<'b>>>(
                 Some(transfer.amount),
                 pda_account,
                 &[&[game_account.key.as_ref(), &[bump_seed]]],
-                token_program,
+                mint_account,
             )?;
         } else {
             return Err(ProcessError::InvalidSlotStakeAccount)?;
@@ -395,3 +625,286 @@ fn handle_transfer<'a, 'b, 'c, I: Iterator<Item = &'a AccountInfo<'b>>>(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        instruction::RaceInstruction,
+        state::PlayerJoin,
+        testing::{self, players_reg_data, process_instruction, TestAccount},
+    };
+    use solana_program::rent::Rent;
+
+    #[test]
+    fn test_ticket_amount() {
+        let satellite = testing::ticket_game(10, None, None);
+
+        assert_eq!(ticket_amount(&satellite, &testing::ticket_game(100, None, None)).unwrap(), 100);
+
+        // Tickets into allowlist games are paid the same way
+        let allowlist_game = GameState {
+            token_mint: satellite.token_mint,
+            entry_type: EntryType::Allowlist {
+                merkle_root: [0; 32],
+                inner: Box::new(EntryType::Ticket { amount: 50, rebuy: None, fee: None }),
+            },
+            ..Default::default()
+        };
+        assert_eq!(ticket_amount(&satellite, &allowlist_game).unwrap(), 50);
+
+        // The target game must be a ticket game of the same token
+        let other_mint_game = GameState {
+            token_mint: Pubkey::new_unique(),
+            ..testing::ticket_game(100, None, None)
+        };
+        assert!(ticket_amount(&satellite, &other_mint_game).is_err());
+        assert!(ticket_amount(&satellite, &testing::cash_game(10, 100)).is_err());
+    }
+
+    #[test]
+    fn test_award_ticket_to_funded_address() {
+        let program_id = Pubkey::new_unique();
+        let alice = Pubkey::new_unique();
+        let mut transactor = TestAccount::wallet(1_000_000_000).signer();
+
+        let game_key = Pubkey::new_unique();
+        let reg_key = Pubkey::new_unique();
+        let (pda, _) = Pubkey::find_program_address(&[game_key.as_ref()], &program_id);
+        let mut game_state = testing::ticket_game(10, None, None);
+        game_state.transactor_addr = Some(transactor.key);
+        game_state.players_reg_account = reg_key;
+        game_state.stake_account = pda;
+        game_state.balances.push(PlayerBalance { player_id: 1, balance: 100 });
+        let mut game = TestAccount::program_account(&program_id, borsh::to_vec(&game_state).unwrap()).with_key(game_key);
+        let players = [PlayerJoin { addr: alice, position: 0, access_version: 1 }];
+        let mut reg = TestAccount::program_account(&program_id, players_reg_data(6, &players)).with_key(reg_key);
+        let mut stake = TestAccount::wallet(100).with_key(pda);
+        let mut pda_account = stake.clone();
+        let mut recipient = TestAccount::wallet(0);
+        let mut token_program = TestAccount::wallet(0);
+        let mut system_program = TestAccount::system_program();
+
+        let mut target_stake = TestAccount::wallet(0);
+        let mut target_state = testing::ticket_game(100, None, None);
+        target_state.stake_account = target_stake.key;
+        let mut target = TestAccount::program_account(&program_id, borsh::to_vec(&target_state).unwrap());
+
+        // Someone sent lamports to the address of the ticket beforehand
+        let (ticket_pda, _) = TicketState::find_address(&program_id, &target.key, &alice);
+        let mut ticket = TestAccount::wallet(1).with_key(ticket_pda);
+
+        let params = SettleParams {
+            settles: Box::new(vec![Settle {
+                player_id: 1,
+                amount: 0,
+                change: Some(BalanceChange::Sub(100)),
+                eject: false,
+            }]),
+            transfer: None,
+            awards: Box::default(),
+            checkpoint: Box::default(),
+            access_version: 1,
+            settle_version: 0,
+            next_settle_version: 1,
+            entry_lock: None,
+            accept_deposits: Box::default(),
+            ticket_awards: Box::new(vec![TicketAward { player_id: 1, game_addr: target.key }]),
+        };
+        process_instruction(
+            &program_id,
+            &mut [
+                &mut transactor,
+                &mut game,
+                &mut reg,
+                &mut stake,
+                &mut pda_account,
+                &mut recipient,
+                &mut token_program,
+                &mut system_program,
+                &mut target,
+                &mut target_stake,
+                &mut ticket,
+            ],
+            RaceInstruction::Settle { params },
+        )
+        .unwrap();
+
+        assert_eq!(stake.lamports, 0);
+        assert_eq!(target_stake.lamports, 100);
+        assert_eq!(target.game_state().ticket_funds, 100);
+        assert_eq!(ticket.owner, program_id);
+        assert_eq!(ticket.lamports, Rent::default().minimum_balance(ticket.data.len()));
+        let ticket_state: TicketState = ticket.state();
+        assert_eq!(ticket_state.owner, alice);
+        assert_eq!(ticket_state.amount, 100);
+        assert_eq!(ticket_state.source_game_addr, game_key);
+        assert_eq!(ticket_state.tournament, None);
+    }
+
+    struct Table {
+        transactor: TestAccount,
+        game: TestAccount,
+        reg: TestAccount,
+        stake: TestAccount,
+        recipient: TestAccount,
+        token_program: TestAccount,
+        system_program: TestAccount,
+        bob: TestAccount,
+    }
+
+    impl Table {
+        /// A game where `alice` and `bob` hold 100 each, only `bob` is paid out.
+        fn new(program_id: &Pubkey) -> Self {
+            let transactor = TestAccount::wallet(1_000_000_000).signer();
+            let alice = TestAccount::wallet(0);
+            let bob = TestAccount::wallet(0);
+            let players = [
+                PlayerJoin { addr: alice.key, position: 0, access_version: 1 },
+                PlayerJoin { addr: bob.key, position: 1, access_version: 2 },
+            ];
+            let reg = TestAccount::program_account(program_id, players_reg_data(6, &players));
+            let game_key = Pubkey::new_unique();
+            let (pda, _) = Pubkey::find_program_address(&[game_key.as_ref()], program_id);
+            let mut game_state = testing::ticket_game(100, None, None);
+            game_state.transactor_addr = Some(transactor.key);
+            game_state.players_reg_account = reg.key;
+            game_state.stake_account = pda;
+            game_state.access_version = 2;
+            game_state.balances = vec![
+                PlayerBalance { player_id: 1, balance: 100 },
+                PlayerBalance { player_id: 2, balance: 100 },
+            ];
+            Table {
+                transactor,
+                game: TestAccount::program_account(program_id, borsh::to_vec(&game_state).unwrap()).with_key(game_key),
+                reg,
+                stake: TestAccount::wallet(200).with_key(pda),
+                recipient: TestAccount::wallet(0),
+                token_program: TestAccount::wallet(0),
+                system_program: TestAccount::system_program(),
+                bob,
+            }
+        }
+
+        fn settle(&mut self, program_id: &Pubkey, settle_version: u64, settles: Vec<Settle>) -> ProgramResult {
+            let params = SettleParams {
+                settles: Box::new(settles),
+                transfer: None,
+                awards: Box::default(),
+                checkpoint: Box::default(),
+                access_version: 2,
+                settle_version,
+                next_settle_version: settle_version + 1,
+                entry_lock: None,
+                accept_deposits: Box::default(),
+                ticket_awards: Box::default(),
+            };
+            let mut pda = self.stake.clone();
+            process_instruction(
+                program_id,
+                &mut [
+                    &mut self.transactor,
+                    &mut self.game,
+                    &mut self.reg,
+                    &mut self.stake,
+                    &mut pda,
+                    &mut self.recipient,
+                    &mut self.token_program,
+                    &mut self.system_program,
+                    &mut self.bob,
+                ],
+                RaceInstruction::Settle { params },
+            )
+        }
+    }
+
+    fn settle(player_id: u64, amount: u64, change: Option<BalanceChange>, eject: bool) -> Settle {
+        Settle { player_id, amount, change, eject }
+    }
+
+    #[test]
+    fn test_settle_and_pay_leaving_player() {
+        let program_id = Pubkey::new_unique();
+        let mut table = Table::new(&program_id);
+
+        // Bob wins 50 from Alice and leaves with all his chips
+        table
+            .settle(
+                &program_id,
+                0,
+                vec![
+                    settle(1, 0, Some(BalanceChange::Sub(50)), false),
+                    settle(2, 150, Some(BalanceChange::Add(50)), false),
+                    settle(2, 0, Some(BalanceChange::Sub(150)), true),
+                ],
+            )
+            .unwrap();
+
+        assert_eq!(table.bob.lamports, 150);
+        assert_eq!(table.stake.lamports, 50);
+        let game_state = table.game.game_state();
+        assert_eq!(game_state.settle_version, 1);
+        assert_eq!(game_state.balances, vec![PlayerBalance { player_id: 1, balance: 50 }]);
+        assert_eq!(players::get_players_count(&table.reg.data).unwrap(), 1);
+        assert!(players::get_player_by_id(&table.reg.data, 2).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_settle_failures() {
+        let program_id = Pubkey::new_unique();
+        let mut table = Table::new(&program_id);
+        let game_data = table.game.data.clone();
+
+        // The settle must continue from the current version
+        assert_eq!(
+            table.settle(&program_id, 1, vec![]),
+            Err(ProcessError::InvalidSettleVersion.into())
+        );
+
+        // No balance goes below zero
+        assert_eq!(
+            table.settle(&program_id, 0, vec![settle(1, 0, Some(BalanceChange::Sub(101)), false)]),
+            Err(ProcessError::InvalidSettleBalance.into())
+        );
+
+        // The changes must sum up to zero
+        assert_eq!(
+            table.settle(&program_id, 0, vec![settle(1, 0, Some(BalanceChange::Add(50)), false)]),
+            Err(ProcessError::UnbalancedGameStake.into())
+        );
+        assert_eq!(
+            table.settle(&program_id, 0, vec![settle(1, 0, Some(BalanceChange::Sub(50)), false)]),
+            Err(ProcessError::UnbalancedGameStake.into())
+        );
+
+        // Paying more than the balance leaves the stake short
+        assert_eq!(
+            table.settle(&program_id, 0, vec![settle(1, 150, Some(BalanceChange::Sub(100)), true)]),
+            Err(ProcessError::UnbalancedGameStake.into())
+        );
+
+        // A paused game only ejects players
+        let mut game_state = table.game.game_state();
+        game_state.game_status = GameStatus::Paused;
+        table.game.data = borsh::to_vec(&game_state).unwrap();
+        assert_eq!(
+            table.settle(
+                &program_id,
+                0,
+                vec![
+                    settle(1, 0, Some(BalanceChange::Sub(50)), false),
+                    settle(2, 0, Some(BalanceChange::Add(50)), false),
+                ],
+            ),
+            Err(ProcessError::GamePaused.into())
+        );
+
+        table.transactor.is_signer = false;
+        table.game.data = game_data;
+        assert_eq!(table.settle(&program_id, 0, vec![]), Err(ProgramError::MissingRequiredSignature));
+
+        assert_eq!(table.stake.lamports, 200);
+        assert_eq!(table.game.game_state().balances.len(), 2);
+    }
+}
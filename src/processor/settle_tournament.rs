@@ -0,0 +1,242 @@
+//! Pay the final prize distribution of a tournament.
+//!
+//! Signed by the transactor of the final table, set by the owner with `SetFinalGame`.  The prizes
+//! are paid from the tournament stake account, and the tournament is finished afterwards.  The
+//! rest of the prize pool is left for the owner to withdraw with `CloseTournament`.
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::ProcessError,
+    events::RaceEvent,
+    state::{GameState, TournamentState},
+    types::SettleTournamentParams,
+};
+
+use super::misc::{general_transfer, next_mint_account, pack_state_to_account, validate_receiver};
+
+#[inline(never)]
+pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], params: SettleTournamentParams) -> ProgramResult {
+    let mut account_iter = accounts.iter();
+
+    let transactor_account = next_account_info(&mut account_iter)?;
+
+    let tournament_account = next_account_info(&mut account_iter)?;
+
+    let game_account = next_account_info(&mut account_iter)?;
+
+    let stake_account = next_account_info(&mut account_iter)?;
+
+    let pda_account = next_account_info(&mut account_iter)?;
+
+    let _token_program = next_account_info(&mut account_iter)?;
+
+    let system_program = next_account_info(&mut account_iter)?;
+
+    if !transactor_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if tournament_account.owner.ne(program_id) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let mut tournament_state = TournamentState::try_from_slice(&tournament_account.try_borrow_data()?)?;
+    if !tournament_state.is_open() {
+        return Err(ProcessError::InvalidTournament)?;
+    }
+
+    if game_account.owner.ne(program_id) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    if !tournament_state.games.contains(game_account.key) || tournament_state.final_game.ne(&Some(*game_account.key)) {
+        msg!("Game {} is not the final table of the tournament", game_account.key);
+        return Err(ProcessError::InvalidTournament)?;
+    }
+
+    if !matches!(game_account.data.borrow()[0], 1 | 3) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
+
+    if game_state.tournament.ne(&Some(*tournament_account.key)) {
+        return Err(ProcessError::InvalidTournament)?;
+    }
+
+    if game_state.transactor_addr.ne(&Some(*transactor_account.key)) {
+        return Err(ProcessError::SignerNotTransactor)?;
+    }
+
+    if stake_account.key.ne(&tournament_state.stake_account) {
+        return Err(ProcessError::InvalidStakeAccount)?;
+    }
+
+    let (pda, bump_seed) = Pubkey::find_program_address(&[tournament_account.key.as_ref()], program_id);
+
+    if pda.ne(pda_account.key) {
+        return Err(ProcessError::InvalidPDA)?;
+    }
+
+    let total = tournament_state.validate_prizes(&params.prizes)?;
+
+    let mint_account = next_mint_account(stake_account, &tournament_state.token_mint, &mut account_iter)?;
+
+    for prize in params.prizes.iter() {
+        let receiver = next_account_info(&mut account_iter)?;
+        validate_receiver(&prize.addr, &tournament_state.token_mint, receiver.key)?;
+        general_transfer(
+            stake_account,
+            receiver,
+            &tournament_state.token_mint,
+            Some(prize.amount),
+            pda_account,
+            &[&[tournament_account.key.as_ref(), &[bump_seed]]],
+            mint_account,
+        )?;
+
+        RaceEvent::TournamentPrizePaid {
+            tournament: *tournament_account.key,
+            player: prize.addr,
+            amount: prize.amount,
+        }
+        .emit()?;
+    }
+
+    tournament_state.prize_pool -= total;
+    tournament_state.is_finished = true;
+
+    pack_state_to_account(tournament_state, tournament_account, transactor_account, system_program)?;
+
+    msg!("Settled tournament {}, paid {}", tournament_account.key, total);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::ProcessError,
+        instruction::RaceInstruction,
+        state::TournamentState,
+        testing::{process_instruction, ticket_game, tournament_state, TestAccount},
+        types::{SettleTournamentParams, TournamentPrize},
+    };
+    use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+    fn settle(prizes: Vec<TournamentPrize>) -> RaceInstruction {
+        RaceInstruction::SettleTournament { params: SettleTournamentParams { prizes } }
+    }
+
+    #[test]
+    fn test_settle_tournament() {
+        let program_id = Pubkey::new_unique();
+        let tournament_key = Pubkey::new_unique();
+        let (pda, _) = Pubkey::find_program_address(&[tournament_key.as_ref()], &program_id);
+        let mut alice = TestAccount::wallet(0);
+        let bob = Pubkey::new_unique();
+
+        let mut transactor = TestAccount::wallet(1_000_000_000).signer();
+        let mut tables: Vec<TestAccount> = (0..2)
+            .map(|_| {
+                let mut game_state = ticket_game(100, None, None);
+                game_state.transactor_addr = Some(transactor.key);
+                game_state.tournament = Some(tournament_key);
+                TestAccount::program_account(&program_id, borsh::to_vec(&game_state).unwrap())
+            })
+            .collect();
+        let mut tournament_state = tournament_state(&[tables[0].key, tables[1].key], &[alice.key, bob], 300);
+        tournament_state.stake_account = pda;
+        tournament_state.final_game = Some(tables[1].key);
+        let mut tournament =
+            TestAccount::program_account(&program_id, borsh::to_vec(&tournament_state).unwrap()).with_key(tournament_key);
+        let mut stake = TestAccount::wallet(300).with_key(pda);
+        let mut pda_account = stake.clone();
+        let mut token_program = TestAccount::wallet(0);
+        let mut system_program = TestAccount::system_program();
+        let alice_key = alice.key;
+        let prizes = || vec![TournamentPrize { addr: alice_key, amount: 200 }];
+
+        // A table other than the final one
+        let result = process_instruction(
+            &program_id,
+            &mut [
+                &mut transactor,
+                &mut tournament,
+                &mut tables[0],
+                &mut stake,
+                &mut pda_account,
+                &mut token_program,
+                &mut system_program,
+                &mut alice,
+            ],
+            settle(prizes()),
+        );
+        assert_eq!(result, Err(ProcessError::InvalidTournament.into()));
+
+        // A look-alike of the final table, not owned by the program
+        let mut fake_table = tables[1].clone();
+        fake_table.owner = Pubkey::new_unique();
+        let result = process_instruction(
+            &program_id,
+            &mut [
+                &mut transactor,
+                &mut tournament,
+                &mut fake_table,
+                &mut stake,
+                &mut pda_account,
+                &mut token_program,
+                &mut system_program,
+                &mut alice,
+            ],
+            settle(prizes()),
+        );
+        assert_eq!(result, Err(ProgramError::InvalidAccountOwner));
+
+        process_instruction(
+            &program_id,
+            &mut [
+                &mut transactor,
+                &mut tournament,
+                &mut tables[1],
+                &mut stake,
+                &mut pda_account,
+                &mut token_program,
+                &mut system_program,
+                &mut alice,
+            ],
+            settle(prizes()),
+        )
+        .unwrap();
+
+        assert_eq!(alice.lamports, 200);
+        assert_eq!(stake.lamports, 100);
+        let tournament_state: TournamentState = tournament.state();
+        assert!(tournament_state.is_finished);
+        assert_eq!(tournament_state.prize_pool, 100);
+
+        // Prizes are paid once
+        let result = process_instruction(
+            &program_id,
+            &mut [
+                &mut transactor,
+                &mut tournament,
+                &mut tables[1],
+                &mut stake,
+                &mut pda_account,
+                &mut token_program,
+                &mut system_program,
+                &mut alice,
+            ],
+            settle(prizes()),
+        );
+        assert_eq!(result, Err(ProcessError::InvalidTournament.into()));
+    }
+}
@@ -0,0 +1,95 @@
+//! Sponsor a game.
+//!
+//! The game owner adds funds to the stake account, which belong to no player.  Settles can draw
+//! the sponsor funds to pay the prizes, e.g. in freerolls.
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction,
+};
+
+use crate::{
+    error::ProcessError,
+    events::RaceEvent,
+    processor::misc::{is_native_mint, next_mint_account, pack_state_to_account, token_transfer},
+    state::GameState,
+    types::SponsorGameParams,
+};
+
+#[inline(never)]
+pub fn process(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: SponsorGameParams,
+) -> ProgramResult {
+    let account_iter = &mut accounts.iter();
+
+    let owner_account = next_account_info(account_iter)?;
+
+    let source_account = next_account_info(account_iter)?;
+
+    let game_account = next_account_info(account_iter)?;
+
+    let stake_account = next_account_info(account_iter)?;
+
+    let _token_program = next_account_info(account_iter)?;
+
+    let system_program = next_account_info(account_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    match game_account.data.borrow()[0] {
+        1 => (),
+        3 => return Err(ProcessError::GamePaused)?,
+        _ => return Err(ProgramError::UninitializedAccount),
+    }
+    let mut game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
+
+    if game_state.owner.ne(owner_account.key) {
+        return Err(ProcessError::InvalidOwner)?;
+    }
+
+    if game_state.stake_account.ne(stake_account.key) {
+        return Err(ProcessError::InvalidStakeAccount)?;
+    }
+
+    if params.amount == 0 {
+        return Err(ProcessError::InvalidPaymentParams)?;
+    }
+
+    let received = if is_native_mint(&game_state.token_mint) {
+        invoke(
+            &system_instruction::transfer(owner_account.key, stake_account.key, params.amount),
+            &[owner_account.clone(), stake_account.clone(), system_program.clone()],
+        )?;
+        params.amount
+    } else {
+        let mint_account = next_mint_account(stake_account, &game_state.token_mint, account_iter)?;
+        token_transfer(source_account, stake_account, owner_account, mint_account, params.amount, &[])?
+    };
+
+    game_state.sponsor_funds = game_state
+        .sponsor_funds
+        .checked_add(received)
+        .ok_or(ProcessError::StakeAmountOverflow)?;
+
+    pack_state_to_account(game_state, game_account, owner_account, system_program)?;
+
+    msg!("Sponsored {} to game {}", params.amount, game_account.key);
+
+    RaceEvent::GameSponsored {
+        game: *game_account.key,
+        sponsor: *owner_account.key,
+        amount: params.amount,
+    }
+    .emit()?;
+
+    Ok(())
+}
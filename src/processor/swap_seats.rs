@@ -0,0 +1,88 @@
+//! Swap the seats of two players, e.g. to balance the tables of a multi-table event.
+//!
+//! Signed by the transactor, regardless of the entry lock.  The access version is bumped, so the
+//! transactor picks up the new positions the same way it picks up new joins.
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::ProcessError,
+    events::RaceEvent,
+    state::{players, GameState},
+    types::SwapSeatsParams,
+};
+
+use super::misc::pack_state_to_account;
+
+#[inline(never)]
+pub fn process(_program_id: &Pubkey, accounts: &[AccountInfo], params: SwapSeatsParams) -> ProgramResult {
+    let account_iter = &mut accounts.iter();
+
+    let transactor_account = next_account_info(account_iter)?;
+
+    let game_account = next_account_info(account_iter)?;
+
+    let players_reg_account = next_account_info(account_iter)?;
+
+    let system_program = next_account_info(account_iter)?;
+
+    if !transactor_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    match game_account.data.borrow()[0] {
+        1 => (),
+        3 => return Err(ProcessError::GamePaused)?,
+        _ => return Err(ProgramError::UninitializedAccount),
+    }
+    let mut game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
+
+    if game_state.transactor_addr.ne(&Some(*transactor_account.key)) {
+        return Err(ProcessError::SignerNotTransactor)?;
+    }
+
+    if players_reg_account.key.ne(&game_state.players_reg_account) {
+        return Err(ProcessError::InvalidPlayersRegAccount)?;
+    }
+
+    if params.player_id == params.other_player_id {
+        return Err(ProcessError::InvalidPosition)?;
+    }
+
+    let mut data = players_reg_account.try_borrow_mut_data()?;
+
+    let (index, _) = players::get_player_by_id(&data, params.player_id)?
+        .ok_or(ProcessError::PlayerNotInGame)?;
+    let (other_index, _) = players::get_player_by_id(&data, params.other_player_id)?
+        .ok_or(ProcessError::PlayerNotInGame)?;
+
+    players::swap_positions(&mut data, index, other_index)?;
+
+    game_state.access_version += 1;
+
+    players::set_versions(&mut data, game_state.access_version, game_state.settle_version)?;
+
+    drop(data);
+
+    let access_version = game_state.access_version;
+
+    pack_state_to_account(game_state, game_account, transactor_account, system_program)?;
+
+    msg!("Swapped seats of players {} and {}", params.player_id, params.other_player_id);
+
+    RaceEvent::SeatsSwapped {
+        game: *game_account.key,
+        player_id: params.player_id,
+        other_player_id: params.other_player_id,
+        access_version,
+    }
+    .emit()?;
+
+    Ok(())
+}
@@ -27,10 +27,10 @@ pub fn process(_programe_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult
 
     // An empty game account can be removed without checking
     if !game_account.data_is_empty() {
-        if game_account.data.borrow()[0] != 1 {
+        if !matches!(game_account.data.borrow()[0], 1 | 3 | 4) {
             return Err(ProgramError::UninitializedAccount);
         }
-        let game_state = GameState::try_from_slice(&game_account.try_borrow_data()?)?;
+        let game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
 
         if game_state.owner.ne(payer.key) {
             return Err(ProcessError::InvalidOwner)?;
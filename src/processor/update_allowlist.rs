@@ -0,0 +1,63 @@
+//! Rotate the Merkle root of a game with allowlist entry.
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    error::ProcessError,
+    events::RaceEvent,
+    processor::misc::pack_state_to_account,
+    state::{EntryType, GameState},
+    types::UpdateAllowlistParams,
+};
+
+#[inline(never)]
+pub fn process(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: UpdateAllowlistParams,
+) -> ProgramResult {
+    let account_iter = &mut accounts.iter();
+
+    let owner_account = next_account_info(account_iter)?;
+
+    let game_account = next_account_info(account_iter)?;
+
+    let system_program = next_account_info(account_iter)?;
+
+    if !owner_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if !matches!(game_account.data.borrow()[0], 1 | 3) {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let mut game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
+
+    if game_state.owner.ne(owner_account.key) {
+        return Err(ProcessError::InvalidOwner)?;
+    }
+
+    let EntryType::Allowlist { merkle_root, .. } = &mut game_state.entry_type else {
+        return Err(ProcessError::InvalidEntryType)?;
+    };
+
+    *merkle_root = params.merkle_root;
+
+    pack_state_to_account(game_state, game_account, owner_account, system_program)?;
+
+    msg!("Updated allowlist of game {}", game_account.key);
+
+    RaceEvent::AllowlistUpdated {
+        game: *game_account.key,
+        merkle_root: params.merkle_root,
+    }
+    .emit()?;
+
+    Ok(())
+}
@@ -1,9 +1,8 @@
-use crate::{error::ProcessError, state::GameState};
+use crate::{error::ProcessError, events::RaceEvent, state::GameState};
 use crate::{
     state::Vote,
     types::{VoteParams, VoteType},
 };
-use borsh::BorshDeserialize;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     clock::Clock,
@@ -33,11 +32,12 @@ pub fn process(
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    if game_account.data.borrow()[0] != 1 {
+    // Servers can still vote out an unresponsive transactor while paused
+    if !matches!(game_account.data.borrow()[0], 1 | 3) {
         return Err(ProgramError::UninitializedAccount);
     }
 
-    let mut game_state = GameState::try_from_slice(&game_account.try_borrow_data()?)?;
+    let mut game_state = GameState::unpack(&game_account.try_borrow_data()?)?;
 
     // Validate voter identity
 
@@ -50,6 +50,13 @@ pub fn process(
         return Err(ProcessError::InvalidVoteeAccount)?;
     }
 
+    let event = RaceEvent::Voted {
+        game: *game_account.key,
+        voter: *voter_account.key,
+        votee: *votee_account.key,
+        vote_type: vote_type.clone(),
+    };
+
     match vote_type {
         VoteType::ServerVoteTransactorDropOff => {
             if game_state
@@ -77,5 +84,5 @@ pub fn process(
 
     pack_state_to_account(game_state, &game_account, &voter_account, &system_program)?;
 
-    Ok(())
+    event.emit()
 }
@@ -3,6 +3,8 @@ mod registry;
 mod server;
 mod profile;
 mod recipient;
+mod tournament;
+mod ticket;
 pub mod players;
 
 pub use game::*;
@@ -10,3 +12,5 @@ pub use registry::*;
 pub use server::*;
 pub use profile::*;
 pub use recipient::*;
+pub use tournament::*;
+pub use ticket::*;
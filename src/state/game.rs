@@ -1,22 +1,193 @@
+use crate::error::ProcessError;
 use crate::types::VoteType;
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
+    msg,
+    program_error::ProgramError,
     program_pack::IsInitialized,
     pubkey::Pubkey,
 };
 
 #[cfg_attr(test, derive(PartialEq, Eq))]
 #[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct RebuyRules {
+    // the max number of rebuys for each player, the first buy-in is not counted
+    pub max_rebuys: u16,
+    // the max sum of a player's buy-ins, add-on included
+    pub max_total_buyin: Option<u64>,
+    // the amount of the one-time add-on, no add-on if not set
+    pub addon_amount: Option<u64>,
+}
+
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[derive(Clone, Debug)]
 pub enum EntryType {
     Cash {
         min_deposit: u64,
         max_deposit: u64,
+        // deposits must be multiples of this amount if set
+        deposit_step: Option<u64>,
+        // table stakes, a deposit can't bring the player's balance above `max_deposit`
+        table_stakes: bool,
     },
     Ticket {
         amount: u64,
+        rebuy: Option<RebuyRules>,
+        // the part of ticket amount routed to the recipient at once, the rest goes to prize pool
+        fee: Option<u64>,
     },
     Gating {
         collection: String,
+    },
+    /// Freeroll, players join without payment.  Prizes come from the sponsor funds and bonuses.
+    Free,
+    /// Only wallets in the Merkle tree of `merkle_root` can join, payments follow `inner`.
+    Allowlist {
+        merkle_root: [u8; 32],
+        inner: Box<EntryType>,
+    },
+}
+
+impl EntryType {
+    /// The entry type that defines how players pay, with access control wrappers unwrapped.
+    pub fn payment_type(&self) -> &EntryType {
+        match self {
+            EntryType::Allowlist { inner, .. } => inner.payment_type(),
+            _ => self,
+        }
+    }
+
+    /// The fee to route to the recipient for a payment of `amount`.  Only the payments of a full
+    /// ticket are charged, add-ons are not.
+    pub fn ticket_fee(&self, amount: u64) -> u64 {
+        match self.payment_type() {
+            EntryType::Ticket { amount: ticket_amount, fee: Some(fee), .. } if amount == *ticket_amount => *fee,
+            _ => 0,
+        }
+    }
+
+    /// Validate the amount of a cash deposit, it must be between the min and max deposit, and a
+    /// multiple of the deposit step if set.  With table stakes, `held` is what the player holds
+    /// already, balance and pending deposits, and the total can't exceed the max deposit.
+    pub fn validate_cash_deposit(&self, amount: u64, held: u64) -> Result<(), ProgramError> {
+        let EntryType::Cash { min_deposit, max_deposit, deposit_step, table_stakes } = self.payment_type() else {
+            return Err(ProcessError::InvalidEntryType)?;
+        };
+
+        if amount < *min_deposit || amount > *max_deposit {
+            msg!("Invalid deposit amount: {}, min: {}, max: {}", amount, min_deposit, max_deposit);
+            return Err(ProcessError::InvalidPaymentParams)?;
+        }
+
+        if deposit_step.is_some_and(|step| !amount.is_multiple_of(step)) {
+            msg!("Deposit amount {} is not a multiple of {:?}", amount, deposit_step);
+            return Err(ProcessError::InvalidPaymentParams)?;
+        }
+
+        if *table_stakes && held.saturating_add(amount) > *max_deposit {
+            msg!("Holding {}, max: {}", held, max_deposit);
+            return Err(ProcessError::BalanceCapExceeded)?;
+        }
+
+        Ok(())
+    }
+
+    /// The fee charged on a deposit, which is recorded with `deposit_amount` after the fee.
+    pub fn deposit_fee(&self, deposit_amount: u64) -> u64 {
+        match self.payment_type() {
+            EntryType::Ticket { amount: ticket_amount, fee: Some(fee), .. } if deposit_amount + fee == *ticket_amount => *fee,
+            _ => 0,
+        }
+    }
+}
+
+/// How [`EntryType`] is laid out on chain.  The first variants keep the layout of the deployed
+/// program, so the accounts and instructions created before the entry rules still decode.  Cash
+/// and ticket entries without any of the new rules are written the same way.
+#[derive(BorshDeserialize, BorshSerialize)]
+enum EntryTypeLayout {
+    Cash {
+        min_deposit: u64,
+        max_deposit: u64,
+    },
+    Ticket {
+        amount: u64,
+    },
+    Gating {
+        collection: String,
+    },
+    Free,
+    Allowlist {
+        merkle_root: [u8; 32],
+        inner: Box<EntryType>,
+    },
+    CashWithRules {
+        min_deposit: u64,
+        max_deposit: u64,
+        deposit_step: Option<u64>,
+        table_stakes: bool,
+    },
+    TicketWithRules {
+        amount: u64,
+        rebuy: Option<RebuyRules>,
+        fee: Option<u64>,
+    },
+}
+
+impl From<EntryType> for EntryTypeLayout {
+    fn from(entry_type: EntryType) -> Self {
+        match entry_type {
+            EntryType::Cash { min_deposit, max_deposit, deposit_step: None, table_stakes: false } => {
+                EntryTypeLayout::Cash { min_deposit, max_deposit }
+            }
+            EntryType::Cash { min_deposit, max_deposit, deposit_step, table_stakes } => EntryTypeLayout::CashWithRules {
+                min_deposit,
+                max_deposit,
+                deposit_step,
+                table_stakes,
+            },
+            EntryType::Ticket { amount, rebuy: None, fee: None } => EntryTypeLayout::Ticket { amount },
+            EntryType::Ticket { amount, rebuy, fee } => EntryTypeLayout::TicketWithRules { amount, rebuy, fee },
+            EntryType::Gating { collection } => EntryTypeLayout::Gating { collection },
+            EntryType::Free => EntryTypeLayout::Free,
+            EntryType::Allowlist { merkle_root, inner } => EntryTypeLayout::Allowlist { merkle_root, inner },
+        }
+    }
+}
+
+impl From<EntryTypeLayout> for EntryType {
+    fn from(layout: EntryTypeLayout) -> Self {
+        match layout {
+            EntryTypeLayout::Cash { min_deposit, max_deposit } => EntryType::Cash {
+                min_deposit,
+                max_deposit,
+                deposit_step: None,
+                table_stakes: false,
+            },
+            EntryTypeLayout::CashWithRules { min_deposit, max_deposit, deposit_step, table_stakes } => EntryType::Cash {
+                min_deposit,
+                max_deposit,
+                deposit_step,
+                table_stakes,
+            },
+            EntryTypeLayout::Ticket { amount } => EntryType::Ticket { amount, rebuy: None, fee: None },
+            EntryTypeLayout::TicketWithRules { amount, rebuy, fee } => EntryType::Ticket { amount, rebuy, fee },
+            EntryTypeLayout::Gating { collection } => EntryType::Gating { collection },
+            EntryTypeLayout::Free => EntryType::Free,
+            EntryTypeLayout::Allowlist { merkle_root, inner } => EntryType::Allowlist { merkle_root, inner },
+        }
+    }
+}
+
+impl BorshSerialize for EntryType {
+    fn serialize<W: borsh::io::Write>(&self, writer: &mut W) -> borsh::io::Result<()> {
+        EntryTypeLayout::from(self.clone()).serialize(writer)
+    }
+}
+
+impl BorshDeserialize for EntryType {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        EntryTypeLayout::deserialize_reader(reader).map(EntryType::from)
     }
 }
 
@@ -25,6 +196,8 @@ impl Default for EntryType {
         EntryType::Cash {
             min_deposit: 1,
             max_deposit: 9999,
+            deposit_step: None,
+            table_stakes: false,
         }
     }
 }
@@ -81,6 +254,15 @@ pub struct PlayerDeposit {
 }
 
 
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[derive(Default, BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct PlayerBuyin {
+    pub addr: Pubkey,
+    pub rebuys: u16,
+    pub total_amount: u64,
+    pub addon: bool,
+}
+
 #[cfg_attr(test, derive(PartialEq, Eq))]
 #[derive(BorshDeserialize, BorshSerialize, Clone, Debug)]
 pub struct Vote {
@@ -104,6 +286,8 @@ pub enum GameStatus {
     Initializing,
     Initialized,
     Closed,
+    Paused,
+    Cancelled,
 }
 
 // State of on-chain GameAccount
@@ -157,6 +341,246 @@ pub struct GameState {
     pub bonuses: Vec<Bonus>,
     // a list of balance snapshot for current checkpoint
     pub balances: Vec<PlayerBalance>,
+    // scheduled start time, in unix timestamp seconds
+    pub start_time: Option<u64>,
+    // players can't join after this time, unless late registration is available
+    pub registration_close_time: Option<u64>,
+    // the end of late registration
+    pub late_registration_until: Option<u64>,
+    // buy-ins of each player, only tracked for ticket games with rebuy rules
+    pub buyins: Vec<PlayerBuyin>,
+    // the invite key derived from the invite secret, it signs the game and player addresses of
+    // every join if set
+    pub invite_key: Option<Pubkey>,
+    // the key which must approve every join if set, could be the owner itself
+    pub gatekeeper: Option<Pubkey>,
+    // funds in stake account that belong to no player, added by the owner
+    pub sponsor_funds: u64,
+    // the tournament this game is a table of, which holds the entries in its stake account
+    pub tournament: Option<Pubkey>,
+    // funds in stake account held for the tickets awarded by satellites, until redeemed
+    pub ticket_funds: u64,
+    // the mint of ticket tokens pre-sold for this game, with the PDA as mint authority
+    pub ticket_mint: Option<Pubkey>,
+    // funds in stake account paid for the ticket tokens in circulation
+    pub presold_funds: u64,
+    // the value of each ticket token, fixed by the first purchase
+    pub ticket_token_value: Option<u64>,
+    // the settle version this session started with, a reopened game continues the versions
+    pub start_settle_version: u64,
+}
+
+impl GameState {
+    /// Decode the game account data, either in the current layout or in the legacy one written
+    /// by the deployed program.  A legacy game is upgraded to the current layout on its next
+    /// write.
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        if let Ok(game_state) = GameState::try_from_slice(data) {
+            return Ok(game_state);
+        }
+        let legacy_state = LegacyGameState::try_from_slice(data)?;
+        Ok(legacy_state.into())
+    }
+
+    /// A game is started by the first settlement of its session.
+    pub fn is_started(&self) -> bool {
+        self.settle_version > self.start_settle_version
+    }
+
+    /// The time after which no player can join, late registration included.
+    pub fn registration_deadline(&self) -> Option<u64> {
+        self.late_registration_until.or(self.registration_close_time)
+    }
+
+    /// Tickets into the game expire once it can't be joined anymore: it's paused or cancelled, or
+    /// its registration is over at `now`.
+    pub fn is_ticket_expired(&self, now: u64) -> bool {
+        matches!(self.game_status, GameStatus::Paused | GameStatus::Cancelled)
+            || self.registration_deadline().is_some_and(|deadline| now >= deadline)
+    }
+
+    /// Record the sale of a ticket token, with `received` by the stake account.  Every token is
+    /// worth the same, so a sale receiving a different amount, e.g. after the transfer fee of the
+    /// mint changed, is rejected.
+    pub fn presell_ticket(&mut self, received: u64) -> Result<(), ProgramError> {
+        let value = *self.ticket_token_value.get_or_insert(received);
+        if value != received {
+            msg!("Ticket token is worth {}, received {}", value, received);
+            return Err(ProcessError::InvalidTicket)?;
+        }
+        self.presold_funds = self
+            .presold_funds
+            .checked_add(received)
+            .ok_or(ProcessError::StakeAmountOverflow)?;
+        Ok(())
+    }
+
+    /// Take the value of a burned ticket token out of the pre-sold funds.
+    pub fn redeem_ticket_token(&mut self) -> Result<u64, ProgramError> {
+        let value = self.ticket_token_value.ok_or(ProcessError::InvalidTicket)?;
+        self.presold_funds = self
+            .presold_funds
+            .checked_sub(value)
+            .ok_or(ProcessError::InvalidTicket)?;
+        Ok(value)
+    }
+
+    /// Release the pre-sold funds once no ticket token is in circulation, with `supply` of the
+    /// ticket mint.  What's left was paid for the tokens burned outside the program, it becomes
+    /// sponsor funds.
+    pub fn release_presold_funds(&mut self, supply: u64) -> Result<(), ProgramError> {
+        if supply > 0 {
+            msg!("{} ticket tokens are in circulation", supply);
+            return Err(ProcessError::OutstandingFunds)?;
+        }
+        self.sponsor_funds = self
+            .sponsor_funds
+            .checked_add(self.presold_funds)
+            .ok_or(ProcessError::StakeAmountOverflow)?;
+        self.presold_funds = 0;
+        Ok(())
+    }
+
+    /// Record a buy-in of the player for ticket games with rebuy rules.  The first buy-in must be
+    /// the ticket amount, the following ones are either rebuys or the add-on.
+    pub fn add_ticket_buyin(&mut self, addr: &Pubkey, amount: u64) -> Result<(), ProgramError> {
+        let EntryType::Ticket { amount: ticket_amount, rebuy: Some(rules), .. } = self.entry_type.payment_type() else {
+            return Ok(());
+        };
+
+        let Some(buyin) = self.buyins.iter_mut().find(|b| b.addr.eq(addr)) else {
+            if amount != *ticket_amount {
+                return Err(ProcessError::InvalidPaymentParams)?;
+            }
+            self.buyins.push(PlayerBuyin {
+                addr: *addr,
+                rebuys: 0,
+                total_amount: amount,
+                addon: false,
+            });
+            return Ok(());
+        };
+
+        let total_amount = buyin
+            .total_amount
+            .checked_add(amount)
+            .ok_or(ProcessError::PlayerBalanceOverflow)?;
+
+        if rules.max_total_buyin.is_some_and(|max| total_amount > max) {
+            msg!("Total buy-in {} exceeds the limit", total_amount);
+            return Err(ProcessError::RebuyLimitExceeded)?;
+        }
+
+        if amount == *ticket_amount && buyin.rebuys < rules.max_rebuys {
+            buyin.rebuys += 1;
+        } else if rules.addon_amount == Some(amount) && !buyin.addon {
+            buyin.addon = true;
+        } else {
+            msg!("No rebuy or add-on available for {}", amount);
+            return Err(ProcessError::RebuyLimitExceeded)?;
+        }
+        buyin.total_amount = total_amount;
+
+        Ok(())
+    }
+
+    /// Revert a buy-in recorded by [`Self::add_ticket_buyin`], used when a deposit is rejected.  The
+    /// `amount` is the deposit amount, which doesn't include the fee.
+    pub fn remove_ticket_buyin(&mut self, addr: &Pubkey, amount: u64) {
+        let amount = amount + self.entry_type.deposit_fee(amount);
+        let EntryType::Ticket { amount: ticket_amount, rebuy: Some(rules), .. } = self.entry_type.payment_type() else {
+            return;
+        };
+
+        let Some(idx) = self.buyins.iter().position(|b| b.addr.eq(addr)) else {
+            return;
+        };
+        let buyin = &mut self.buyins[idx];
+
+        if buyin.total_amount <= amount {
+            self.buyins.remove(idx);
+            return;
+        }
+
+        if buyin.addon && rules.addon_amount == Some(amount) && (amount != *ticket_amount || buyin.rebuys == 0) {
+            buyin.addon = false;
+        } else {
+            buyin.rebuys = buyin.rebuys.saturating_sub(1);
+        }
+        buyin.total_amount -= amount;
+    }
+}
+
+// State of the game accounts written by the deployed program, which ends at `balances`
+#[derive(BorshDeserialize, BorshSerialize)]
+struct LegacyGameState {
+    game_status: GameStatus,
+    version: String,
+    title: String,
+    bundle_addr: Pubkey,
+    stake_account: Pubkey,
+    owner: Pubkey,
+    token_mint: Pubkey,
+    transactor_addr: Option<Pubkey>,
+    access_version: u64,
+    settle_version: u64,
+    max_players: u16,
+    players_reg_account: Pubkey,
+    deposits: Vec<PlayerDeposit>,
+    servers: Vec<ServerJoin>,
+    data_len: u32,
+    data: Vec<u8>,
+    votes: Vec<Vote>,
+    unlock_time: Option<u64>,
+    entry_type: EntryType,
+    recipient_addr: Pubkey,
+    checkpoint: Vec<u8>,
+    entry_lock: EntryLock,
+    bonuses: Vec<Bonus>,
+    balances: Vec<PlayerBalance>,
+}
+
+impl From<LegacyGameState> for GameState {
+    fn from(legacy_state: LegacyGameState) -> Self {
+        GameState {
+            game_status: legacy_state.game_status,
+            version: legacy_state.version,
+            title: legacy_state.title,
+            bundle_addr: legacy_state.bundle_addr,
+            stake_account: legacy_state.stake_account,
+            owner: legacy_state.owner,
+            token_mint: legacy_state.token_mint,
+            transactor_addr: legacy_state.transactor_addr,
+            access_version: legacy_state.access_version,
+            settle_version: legacy_state.settle_version,
+            max_players: legacy_state.max_players,
+            players_reg_account: legacy_state.players_reg_account,
+            deposits: legacy_state.deposits,
+            servers: legacy_state.servers,
+            data_len: legacy_state.data_len,
+            data: legacy_state.data,
+            votes: legacy_state.votes,
+            unlock_time: legacy_state.unlock_time,
+            entry_type: legacy_state.entry_type,
+            recipient_addr: legacy_state.recipient_addr,
+            checkpoint: legacy_state.checkpoint,
+            entry_lock: legacy_state.entry_lock,
+            bonuses: legacy_state.bonuses,
+            balances: legacy_state.balances,
+            ..Default::default()
+        }
+    }
+}
+
+// What remains in a closed game account, so the account can be reopened later with versions
+// continuing from the last session
+#[cfg_attr(test, derive(PartialEq, Clone))]
+#[derive(Default, BorshDeserialize, BorshSerialize, Debug)]
+pub struct ClosedGameState {
+    pub game_status: GameStatus,
+    pub owner: Pubkey,
+    pub access_version: u64,
+    pub settle_version: u64,
 }
 
 impl IsInitialized for GameState {
@@ -164,3 +588,183 @@ impl IsInitialized for GameState {
         self.game_status == GameStatus::Initialized
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::ticket_game;
+
+    fn rebuy(max_rebuys: u16, max_total_buyin: Option<u64>, addon_amount: Option<u64>) -> Option<RebuyRules> {
+        Some(RebuyRules {
+            max_rebuys,
+            max_total_buyin,
+            addon_amount,
+        })
+    }
+
+    #[test]
+    fn test_ticket_buyin_rebuys() {
+        let mut game_state = ticket_game(100, rebuy(1, None, None), None);
+        let addr = Pubkey::new_unique();
+        game_state.add_ticket_buyin(&addr, 100).unwrap();
+        game_state.add_ticket_buyin(&addr, 100).unwrap();
+        assert!(game_state.add_ticket_buyin(&addr, 100).is_err());
+        assert_eq!(game_state.buyins[0].rebuys, 1);
+        assert_eq!(game_state.buyins[0].total_amount, 200);
+
+        game_state.remove_ticket_buyin(&addr, 100);
+        assert_eq!(game_state.buyins[0].rebuys, 0);
+        game_state.add_ticket_buyin(&addr, 100).unwrap();
+    }
+
+    #[test]
+    fn test_ticket_buyin_addon_and_total() {
+        let mut game_state = ticket_game(100, rebuy(3, Some(250), Some(50)), None);
+        let addr = Pubkey::new_unique();
+        assert!(game_state.add_ticket_buyin(&addr, 50).is_err());
+        game_state.add_ticket_buyin(&addr, 100).unwrap();
+        game_state.add_ticket_buyin(&addr, 50).unwrap();
+        assert!(game_state.add_ticket_buyin(&addr, 50).is_err());
+        game_state.add_ticket_buyin(&addr, 100).unwrap();
+        assert!(game_state.add_ticket_buyin(&addr, 100).is_err());
+        assert_eq!(game_state.buyins[0].total_amount, 250);
+
+        game_state.remove_ticket_buyin(&addr, 50);
+        assert!(!game_state.buyins[0].addon);
+        game_state.remove_ticket_buyin(&addr, 100);
+        game_state.remove_ticket_buyin(&addr, 100);
+        assert!(game_state.buyins.is_empty());
+    }
+
+    #[test]
+    fn test_ticket_tokens() {
+        let mut game_state = ticket_game(100, rebuy(0, None, None), None);
+        assert!(game_state.redeem_ticket_token().is_err());
+
+        game_state.presell_ticket(90).unwrap();
+        game_state.presell_ticket(90).unwrap();
+        assert!(game_state.presell_ticket(89).is_err());
+        assert_eq!(game_state.presold_funds, 180);
+
+        assert_eq!(game_state.redeem_ticket_token().unwrap(), 90);
+        assert!(game_state.release_presold_funds(1).is_err());
+
+        // The other token is burned outside the program
+        game_state.release_presold_funds(0).unwrap();
+        assert_eq!(game_state.presold_funds, 0);
+        assert_eq!(game_state.sponsor_funds, 90);
+        assert!(game_state.redeem_ticket_token().is_err());
+    }
+
+    #[test]
+    fn test_entry_type_layout() {
+        let cash = EntryType::default();
+        let data = borsh::to_vec(&cash).unwrap();
+        assert_eq!(data.len(), 17);
+        assert_eq!(data[0], 0);
+        assert_eq!(EntryType::try_from_slice(&data).unwrap(), cash);
+
+        let ticket = EntryType::Ticket { amount: 100, rebuy: None, fee: None };
+        assert_eq!(borsh::to_vec(&ticket).unwrap(), [&[1u8][..], &100u64.to_le_bytes()].concat());
+
+        let entry_types = [
+            EntryType::Cash { min_deposit: 10, max_deposit: 100, deposit_step: Some(10), table_stakes: true },
+            EntryType::Ticket { amount: 100, rebuy: None, fee: Some(10) },
+            EntryType::Allowlist { merkle_root: [1; 32], inner: Box::new(EntryType::Free) },
+        ];
+        for entry_type in entry_types {
+            let data = borsh::to_vec(&entry_type).unwrap();
+            assert_eq!(EntryType::try_from_slice(&data).unwrap(), entry_type);
+        }
+    }
+
+    #[test]
+    fn test_unpack_legacy_game_state() {
+        let legacy_state = LegacyGameState {
+            game_status: GameStatus::Initialized,
+            version: "0.2.6".to_string(),
+            title: "Legacy".to_string(),
+            bundle_addr: Pubkey::new_unique(),
+            stake_account: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            token_mint: Pubkey::new_unique(),
+            transactor_addr: None,
+            access_version: 3,
+            settle_version: 2,
+            max_players: 6,
+            players_reg_account: Pubkey::new_unique(),
+            deposits: vec![PlayerDeposit { amount: 100, ..Default::default() }],
+            servers: vec![],
+            data_len: 0,
+            data: vec![],
+            votes: vec![],
+            unlock_time: None,
+            entry_type: EntryType::Ticket { amount: 100, rebuy: None, fee: None },
+            recipient_addr: Pubkey::new_unique(),
+            checkpoint: vec![],
+            entry_lock: EntryLock::Open,
+            bonuses: vec![],
+            balances: vec![PlayerBalance { player_id: 1, balance: 100 }],
+        };
+        let data = borsh::to_vec(&legacy_state).unwrap();
+        assert!(GameState::try_from_slice(&data).is_err());
+
+        let game_state = GameState::unpack(&data).unwrap();
+        assert_eq!(game_state.owner, legacy_state.owner);
+        assert_eq!(game_state.settle_version, 2);
+        assert_eq!(game_state.deposits.len(), 1);
+        assert_eq!(game_state.balances[0].balance, 100);
+        assert_eq!(game_state.entry_type, legacy_state.entry_type);
+        assert_eq!(game_state.ticket_funds, 0);
+
+        // The next write upgrades the account to the current layout
+        let data = borsh::to_vec(&game_state).unwrap();
+        assert_eq!(GameState::try_from_slice(&data).unwrap(), game_state);
+        assert!(GameState::unpack(&data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_validate_cash_deposit() {
+        let entry_type = EntryType::Cash { min_deposit: 100, max_deposit: 1000, deposit_step: Some(50), table_stakes: true };
+        entry_type.validate_cash_deposit(150, 0).unwrap();
+        entry_type.validate_cash_deposit(1000, 0).unwrap();
+        assert!(entry_type.validate_cash_deposit(50, 0).is_err());
+        assert!(entry_type.validate_cash_deposit(1050, 0).is_err());
+        assert!(entry_type.validate_cash_deposit(120, 0).is_err());
+        // Table stakes
+        entry_type.validate_cash_deposit(200, 800).unwrap();
+        assert!(entry_type.validate_cash_deposit(250, 800).is_err());
+        assert!(EntryType::Free.validate_cash_deposit(100, 0).is_err());
+    }
+
+    #[test]
+    fn test_is_started() {
+        let mut game_state = ticket_game(100, rebuy(0, None, None), None);
+        assert!(!game_state.is_started());
+        game_state.settle_version = 1;
+        assert!(game_state.is_started());
+
+        // Reopened for a new session
+        game_state.start_settle_version = 2;
+        game_state.settle_version = 2;
+        assert!(!game_state.is_started());
+    }
+
+    #[test]
+    fn test_is_ticket_expired() {
+        let mut game_state = ticket_game(100, rebuy(0, None, None), None);
+        game_state.game_status = GameStatus::Initialized;
+        assert!(!game_state.is_ticket_expired(100));
+
+        game_state.registration_close_time = Some(100);
+        assert!(!game_state.is_ticket_expired(99));
+        assert!(game_state.is_ticket_expired(100));
+        game_state.late_registration_until = Some(200);
+        assert!(!game_state.is_ticket_expired(100));
+
+        game_state.game_status = GameStatus::Paused;
+        assert!(game_state.is_ticket_expired(100));
+        game_state.game_status = GameStatus::Cancelled;
+        assert!(game_state.is_ticket_expired(100));
+    }
+}
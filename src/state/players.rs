@@ -1,17 +1,32 @@
 /// Helper fuctions for reading and mutating players information of a game.  Each game has one
-/// players registration account(players_reg_account), which contains a list of all [[PlayerJoin]]s.
+/// players registration account(players_reg_account), which contains a list of all [`PlayerJoin`]s.
 /// Each PlayerJoin is stored as 42 bytes.
 ///
 /// The account structure:
 ///
-/// [u64][u64][usize][128byte][4byte][PlayerJoin*]
-/// |    |     |      |       |      |___ The array of PlayerJoins, each uses 42 bytes.
-/// |    |     |      |       |___ The total number of slots, empty slots included.
-/// |    |     |      |___ The position flags, 0 stands for empty, 1 stands for occupied.
-/// |    |     |___ The number of players. It is legal to have some empty slots in the middle, those are not counted.
+/// [u64][u64][u32][u32][u32][u32][flags_len bytes][PlayerJoin*]
+/// |    |    |    |    |    |    |                |___ The array of PlayerJoins, each uses 42 bytes.
+/// |    |    |    |    |    |    |___ The position flags, 0 stands for empty, 1 stands for occupied.
+/// |    |    |    |    |    |___ The length of position flags, enough for `max_players`.
+/// |    |    |    |    |___ The total number of slots, empty slots included.
+/// |    |    |    |___ The layout version, always 2.
+/// |    |    |___ The number of players. It is legal to have some empty slots in the middle, those are not counted.
 /// |    | __ The settle_version. Updated every time a settlement is procced.
 /// |___ The access_version. Updated every time a new player joined.
 ///
+/// Legacy accounts, created before the layout version, use a fixed head instead:
+///
+/// [u64][u64][usize][128byte][4byte][PlayerJoin*]
+///
+/// Their layout version reads 0, as it's the high half of the players count.  Note the position
+/// flags of legacy accounts are read and written at offset 32, rather than 24, thus positions are
+/// limited to 1024.  Legacy accounts can be migrated with [`migrate_legacy`].
+///
+/// In the versioned layout, players are sorted by id (`access_version`) for binary search.  Slots
+/// are append-only: a removed player leaves a tombstone which keeps the id with the rest zeroed,
+/// and only the never used slots at the end, all zeroed, can be filled.  Tombstones are dropped
+/// by [`compact`].  Legacy accounts reuse empty slots and are scanned linearly.
+///
 use crate::error::ProcessError;
 use crate::state::PlayerJoin;
 use borsh::BorshDeserialize;
@@ -41,6 +56,18 @@ const SLOTS_COUNT_OFFSET: usize = POSITION_FLAGS_OFFSET + POSITION_FLAGS_LEN;
 
 pub const HEAD_LEN: usize = SLOTS_COUNT_OFFSET + SLOTS_COUNT_LEN;
 
+// lens and offsets for the versioned layout
+const V2_COUNT_LEN: usize = 4;
+const LAYOUT_VERSION_OFFSET: usize = COUNT_OFFSET + V2_COUNT_LEN;
+const LAYOUT_VERSION_LEN: usize = 4;
+const V2_SLOTS_COUNT_OFFSET: usize = LAYOUT_VERSION_OFFSET + LAYOUT_VERSION_LEN;
+const FLAGS_LEN_OFFSET: usize = V2_SLOTS_COUNT_OFFSET + SLOTS_COUNT_LEN;
+const FLAGS_LEN_LEN: usize = 4;
+const V2_POSITION_FLAGS_OFFSET: usize = FLAGS_LEN_OFFSET + FLAGS_LEN_LEN;
+
+pub const LEGACY_LAYOUT_VERSION: u32 = 0;
+pub const LAYOUT_VERSION: u32 = 2;
+
 #[cfg_attr(test, derive(PartialEq, Eq))]
 #[derive(Debug, BorshDeserialize)]
 pub struct PlayerJoinWithoutKey {
@@ -51,6 +78,62 @@ pub struct PlayerJoinWithoutKey {
     pub access_version: u64,
 }
 
+/// Where the fields are, for either layout.
+struct Layout {
+    count_len: usize,
+    slots_count_offset: usize,
+    flags_offset: usize,
+    flags_len: usize,
+    players_offset: usize,
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, ProgramError> {
+    Ok(u32::try_from_slice(&data[offset..(offset + 4)])?)
+}
+
+pub fn get_layout_version(data: &[u8]) -> Result<u32, ProgramError> {
+    if data.len() < V2_POSITION_FLAGS_OFFSET {
+        return Err(ProcessError::MalformedPlayersRegAccount)?;
+    }
+    read_u32(data, LAYOUT_VERSION_OFFSET)
+}
+
+fn get_layout(data: &[u8]) -> Result<Layout, ProgramError> {
+    match get_layout_version(data)? {
+        LEGACY_LAYOUT_VERSION if data.len() >= HEAD_LEN => Ok(Layout {
+            count_len: COUNT_LEN,
+            slots_count_offset: SLOTS_COUNT_OFFSET,
+            flags_offset: POSITION_OFFSET,
+            flags_len: POSITION_FLAGS_LEN,
+            players_offset: HEAD_LEN,
+        }),
+        LAYOUT_VERSION => {
+            let flags_len = read_u32(data, FLAGS_LEN_OFFSET)? as usize;
+            let players_offset = V2_POSITION_FLAGS_OFFSET + flags_len;
+            if data.len() < players_offset {
+                return Err(ProcessError::MalformedPlayersRegAccount)?;
+            }
+            Ok(Layout {
+                count_len: V2_COUNT_LEN,
+                slots_count_offset: V2_SLOTS_COUNT_OFFSET,
+                flags_offset: V2_POSITION_FLAGS_OFFSET,
+                flags_len,
+                players_offset,
+            })
+        }
+        _ => Err(ProcessError::MalformedPlayersRegAccount)?,
+    }
+}
+
+fn flags_len(max_players: u16) -> usize {
+    (max_players as usize).div_ceil(8)
+}
+
+/// The length of the head with position flags for `max_players`.
+pub fn head_len(max_players: u16) -> usize {
+    V2_POSITION_FLAGS_OFFSET + flags_len(max_players)
+}
+
 pub fn validate_account_data(data: &[u8]) -> Result<(), ProgramError> {
     if data.len() != HEAD_LEN {
         return Err(ProcessError::InvalidPlayersRegAccountForInit)?;
@@ -59,6 +142,48 @@ pub fn validate_account_data(data: &[u8]) -> Result<(), ProgramError> {
     Ok(())
 }
 
+/// Write an empty head of the versioned layout.  The account must be resized to [`head_len`]
+/// beforehand, the versions are kept.
+pub fn init_account_data(data: &mut [u8], max_players: u16) -> Result<(), ProgramError> {
+    if data.len() != head_len(max_players) {
+        return Err(ProcessError::InvalidPlayersRegAccountForInit)?;
+    }
+    data[COUNT_OFFSET..].fill(0);
+    borsh::to_writer(&mut data[LAYOUT_VERSION_OFFSET..(LAYOUT_VERSION_OFFSET+LAYOUT_VERSION_LEN)], &LAYOUT_VERSION)?;
+    borsh::to_writer(&mut data[FLAGS_LEN_OFFSET..(FLAGS_LEN_OFFSET+FLAGS_LEN_LEN)], &(flags_len(max_players) as u32))?;
+    Ok(())
+}
+
+/// Convert a legacy account data to the versioned layout, with position flags for `max_players`.
+/// The flags are rebuilt from the positions of players, and the versions are kept.  Empty slots
+/// are dropped and the players are sorted by id.
+pub fn migrate_legacy(data: &[u8], max_players: u16) -> Result<Vec<u8>, ProgramError> {
+    if get_layout_version(data)? != LEGACY_LAYOUT_VERSION {
+        return Err(ProcessError::MalformedPlayersRegAccount)?;
+    }
+    let mut players = Vec::with_capacity(get_players_count(data)?);
+    for index in 0..get_slots_count(data)? {
+        if let Some(player) = get_player_by_index(data, index)? {
+            players.push(PlayerJoin {
+                addr: player.addr,
+                position: player.position,
+                access_version: player.access_version,
+            });
+        }
+    }
+    players.sort_by_key(|p| p.access_version);
+
+    let head_len = head_len(max_players);
+    let mut new_data = vec![0; head_len + slots_len(players.len())];
+    new_data[..COUNT_OFFSET].copy_from_slice(&data[..COUNT_OFFSET]);
+    init_account_data(&mut new_data[..head_len], max_players)?;
+    add_slots(&mut new_data, players.len())?;
+    for player in players.iter() {
+        add_player(&mut new_data, player)?.ok_or(ProcessError::MalformedPlayersRegAccount)?;
+    }
+    Ok(new_data)
+}
+
 pub fn set_versions(data: &mut [u8], access_version: u64, settle_version: u64) -> Result<(), ProgramError> {
     msg!("Set versions, access version = {}, settle version = {}", access_version, settle_version);
     borsh::to_writer(&mut data[ACCESS_VERSION_OFFSET..(ACCESS_VERSION_OFFSET+VERSION_LEN)], &access_version)?;
@@ -67,12 +192,27 @@ pub fn set_versions(data: &mut [u8], access_version: u64, settle_version: u64) -
 }
 
 pub fn get_players_count(data: &[u8]) -> Result<usize, ProgramError> {
-    Ok(usize::try_from_slice(&data[COUNT_OFFSET..(COUNT_OFFSET+COUNT_LEN)])?)
+    let layout = get_layout(data)?;
+    if layout.count_len == COUNT_LEN {
+        Ok(usize::try_from_slice(&data[COUNT_OFFSET..(COUNT_OFFSET+COUNT_LEN)])?)
+    } else {
+        Ok(read_u32(data, COUNT_OFFSET)? as usize)
+    }
+}
+
+fn set_players_count(data: &mut [u8], size: usize) -> Result<(), ProgramError> {
+    let layout = get_layout(data)?;
+    if layout.count_len == COUNT_LEN {
+        borsh::to_writer(&mut data[COUNT_OFFSET..(COUNT_OFFSET+COUNT_LEN)], &size)?;
+    } else {
+        borsh::to_writer(&mut data[COUNT_OFFSET..(COUNT_OFFSET+V2_COUNT_LEN)], &(size as u32))?;
+    }
+    Ok(())
 }
 
 pub fn increase_players_count(data: &mut [u8]) -> Result<usize, ProgramError> {
     let size = get_players_count(&data)?;
-    borsh::to_writer(&mut data[COUNT_OFFSET..(COUNT_OFFSET+COUNT_LEN)], &(size + 1))?;
+    set_players_count(data, size + 1)?;
     Ok(size + 1)
 }
 
@@ -81,7 +221,7 @@ pub fn decrease_players_count(data: &mut [u8]) -> Result<usize, ProgramError> {
     if size == 0 {
         return Err(ProcessError::CantDecreasePlayersRegAccountSize)?;
     }
-    borsh::to_writer(&mut data[COUNT_OFFSET..(COUNT_OFFSET+COUNT_LEN)], &(size - 1))?;
+    set_players_count(data, size - 1)?;
     Ok(size - 1)
 }
 
@@ -94,7 +234,7 @@ pub fn get_player_by_index(
     if index >= slots_count {
         return Ok(None);
     }
-    let start = index * PLAYER_INFO_LEN + HEAD_LEN;
+    let start = index * PLAYER_INFO_LEN + get_layout(data)?.players_offset;
     let addr_end = start + PUBKEY_LEN;
     let end = start + PLAYER_INFO_LEN;
     if data[start..addr_end].iter().any(|n| *n != 0) {
@@ -109,11 +249,15 @@ pub fn get_player_by_id(
     data: &[u8],
     id: u64,
 ) -> Result<Option<(usize, PlayerJoinWithoutKey)>, ProgramError> {
+    if get_layout_version(data)? == LAYOUT_VERSION {
+        return get_player_by_id_sorted(data, id);
+    }
     let mut id_v = [0u8; 8];
     borsh::to_writer(&mut id_v[..], &id)?;
+    let players_offset = get_layout(data)?.players_offset;
     let mut i = 0;
-    while HEAD_LEN + PLAYER_INFO_LEN * i < data.len() {
-        let start = HEAD_LEN + PLAYER_INFO_LEN * i;
+    while players_offset + PLAYER_INFO_LEN * i < data.len() {
+        let start = players_offset + PLAYER_INFO_LEN * i;
         let id_start = start + ID_OFFSET;
         let id_end = id_start + ID_LEN;
         if &id_v == &data[id_start..id_end] {
@@ -129,14 +273,45 @@ pub fn get_player_by_id(
     return Ok(None);
 }
 
-#[allow(unused)]
+fn read_player_id(data: &[u8], players_offset: usize, index: usize) -> Result<u64, ProgramError> {
+    let id_start = players_offset + index * PLAYER_INFO_LEN + ID_OFFSET;
+    Ok(u64::try_from_slice(&data[id_start..(id_start + ID_LEN)])?)
+}
+
+/// Binary search by id in the versioned layout.  The unused slots at the end, with id 0, are
+/// ordered last.
+fn get_player_by_id_sorted(
+    data: &[u8],
+    id: u64,
+) -> Result<Option<(usize, PlayerJoinWithoutKey)>, ProgramError> {
+    let players_offset = get_layout(data)?.players_offset;
+    let (mut lo, mut hi) = (0, get_slots_count(data)?);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        let mid_id = match read_player_id(data, players_offset, mid)? {
+            0 => u64::MAX,
+            mid_id => mid_id,
+        };
+        if mid_id < id {
+            lo = mid + 1;
+        } else if mid_id > id {
+            hi = mid;
+        } else {
+            // A tombstone of a removed player
+            return Ok(get_player_by_index(data, mid)?.map(|p| (mid, p)));
+        }
+    }
+    Ok(None)
+}
+
 pub fn get_player_by_addr(
     data: &[u8],
     addr: &Pubkey,
 ) -> Result<Option<(usize, PlayerJoinWithoutKey)>, ProgramError> {
+    let players_offset = get_layout(data)?.players_offset;
     let mut i = 0;
-    while HEAD_LEN + PLAYER_INFO_LEN * i < data.len() {
-        let start = HEAD_LEN + PLAYER_INFO_LEN * i;
+    while players_offset + PLAYER_INFO_LEN * i < data.len() {
+        let start = players_offset + PLAYER_INFO_LEN * i;
         let addr_end = start + PUBKEY_LEN;
         if addr.as_ref() == &data[start..addr_end] {
             return Ok(Some((
@@ -153,9 +328,10 @@ pub fn get_player_by_addr(
 
 pub fn is_player_joined(data: &[u8], addr: &Pubkey) -> Result<bool, ProgramError> {
     let slots_count = get_slots_count(data)?;
+    let players_offset = get_layout(data)?.players_offset;
     // Find a slot
     for i in 0..slots_count {
-        let start = i * PLAYER_INFO_LEN + HEAD_LEN;
+        let start = i * PLAYER_INFO_LEN + players_offset;
         let addr_end = start + PUBKEY_LEN;
         if addr.as_ref() == &data[start..addr_end] {
             return Ok(true);
@@ -165,11 +341,9 @@ pub fn is_player_joined(data: &[u8], addr: &Pubkey) -> Result<bool, ProgramError
 }
 
 pub fn is_position_occupied(data: &[u8], position: u16) -> Result<bool, ProgramError> {
-    if data.len() < HEAD_LEN {
-        return Err(ProcessError::MalformedPlayersRegAccount)?;
-    }
-    // We support at most 1024 players
-    if position > 1024 {
+    let layout = get_layout(data)?;
+    // Positions beyond the flags are not available
+    if position as usize >= layout.flags_len * 8 {
         return Ok(true);
     }
 
@@ -177,7 +351,7 @@ pub fn is_position_occupied(data: &[u8], position: u16) -> Result<bool, ProgramE
     let o = position % 8;
     let f = 1 << o as u8;
 
-    if f & (&data[POSITION_OFFSET + i as usize]) != 0 {
+    if f & (&data[layout.flags_offset + i as usize]) != 0 {
         return Ok(true);
     }
 
@@ -185,8 +359,9 @@ pub fn is_position_occupied(data: &[u8], position: u16) -> Result<bool, ProgramE
 }
 
 pub fn set_position_flag(data: &mut [u8], position: u16, flag: bool) -> Result<(), ProgramError> {
-    if data.len() < HEAD_LEN {
-        return Err(ProcessError::MalformedPlayersRegAccount)?;
+    let layout = get_layout(data)?;
+    if position as usize >= layout.flags_len * 8 {
+        return Err(ProcessError::InvalidPosition)?;
     }
 
     let i = position / 8;
@@ -194,25 +369,50 @@ pub fn set_position_flag(data: &mut [u8], position: u16, flag: bool) -> Result<(
     let f = 1 << o as u8;
 
     if flag {
-        data[POSITION_OFFSET + i as usize] |= f;
+        data[layout.flags_offset + i as usize] |= f;
     } else {
-        data[POSITION_OFFSET + i as usize] &= !f;
+        data[layout.flags_offset + i as usize] &= !f;
     }
     return Ok(());
 }
 
 pub fn get_available_position(data: &[u8], max_players: u16) -> Result<u16, ProgramError> {
     for position in 0u16..max_players {
-        let i = position / 8;
-        let o = position % 8;
-        let f = 1 << o as u8;
-        if data[POSITION_OFFSET + i as usize] & f == 0 {
-            return Ok(i * 8 + o);
+        if !is_position_occupied(data, position)? {
+            return Ok(position);
         }
     }
     return Err(ProcessError::GameFullAlready)?;
 }
 
+/// Move the player at `index` to an empty `position`, the position flags are updated.
+pub fn change_position(data: &mut [u8], index: usize, position: u16) -> Result<(), ProgramError> {
+    let player = get_player_by_index(data, index)?.ok_or(ProcessError::PlayerNotInGame)?;
+    if player.position == position {
+        return Ok(());
+    }
+    if is_position_occupied(data, position)? {
+        return Err(ProcessError::PositionTakenAlready)?;
+    }
+    let pos_start = index * PLAYER_INFO_LEN + get_layout(data)?.players_offset + POSITION_OFFSET;
+    borsh::to_writer(&mut data[pos_start..(pos_start + POSITION_LEN)], &position)?;
+    set_position_flag(data, player.position, false)?;
+    set_position_flag(data, position, true)?;
+    Ok(())
+}
+
+/// Swap the positions of two players, the position flags are unchanged.
+pub fn swap_positions(data: &mut [u8], index: usize, other_index: usize) -> Result<(), ProgramError> {
+    let player = get_player_by_index(data, index)?.ok_or(ProcessError::PlayerNotInGame)?;
+    let other = get_player_by_index(data, other_index)?.ok_or(ProcessError::PlayerNotInGame)?;
+    let players_offset = get_layout(data)?.players_offset;
+    let pos_start = index * PLAYER_INFO_LEN + players_offset + POSITION_OFFSET;
+    borsh::to_writer(&mut data[pos_start..(pos_start + POSITION_LEN)], &other.position)?;
+    let pos_start = other_index * PLAYER_INFO_LEN + players_offset + POSITION_OFFSET;
+    borsh::to_writer(&mut data[pos_start..(pos_start + POSITION_LEN)], &player.position)?;
+    Ok(())
+}
+
 pub fn increase_size_set_position_flag(data: &mut [u8], position: u16) -> Result<(), ProgramError> {
     increase_players_count(data)?;
     set_position_flag(data, position, true)?;
@@ -220,25 +420,67 @@ pub fn increase_size_set_position_flag(data: &mut [u8], position: u16) -> Result
 }
 
 pub fn get_slots_count(data: &[u8]) -> Result<usize, ProgramError> {
-    let slots_count = u32::try_from_slice(&data[SLOTS_COUNT_OFFSET..(SLOTS_COUNT_OFFSET+SLOTS_COUNT_LEN)])?;
-    Ok(slots_count as usize)
+    let slots_count_offset = get_layout(data)?.slots_count_offset;
+    Ok(read_u32(data, slots_count_offset)? as usize)
+}
+
+fn set_slots_count(data: &mut [u8], slots_count: usize) -> Result<(), ProgramError> {
+    let slots_count_offset = get_layout(data)?.slots_count_offset;
+    borsh::to_writer(&mut data[slots_count_offset..(slots_count_offset+SLOTS_COUNT_LEN)], &(slots_count as u32))?;
+    Ok(())
 }
 
 pub fn increase_slots_count(data: &mut [u8]) -> Result<(), ProgramError> {
     let slots_count = get_slots_count(&data)?;
-    borsh::to_writer(&mut data[SLOTS_COUNT_OFFSET..(SLOTS_COUNT_OFFSET+SLOTS_COUNT_LEN)], &(slots_count as u32+1))?;
-    Ok(())
+    set_slots_count(data, slots_count + 1)
+}
+
+/// The number of slots that [`add_player`] can fill.  For the versioned layout, tombstones are
+/// not counted.
+pub fn get_free_slots_count(data: &[u8]) -> Result<usize, ProgramError> {
+    let slots_count = get_slots_count(data)?;
+    if get_layout_version(data)? != LAYOUT_VERSION {
+        return Ok(slots_count.saturating_sub(get_players_count(data)?));
+    }
+    let players_offset = get_layout(data)?.players_offset;
+    let mut free = 0;
+    while free < slots_count && read_player_id(data, players_offset, slots_count - free - 1)? == 0 {
+        free += 1;
+    }
+    Ok(free)
+}
+
+/// The length of `n` slots, by which the account should grow before [`add_slots`].
+pub fn slots_len(n: usize) -> usize {
+    n * PLAYER_INFO_LEN
+}
+
+/// Add `n` empty slots at the end.  The account must be grown by [`slots_len`] beforehand.
+pub fn add_slots(data: &mut [u8], n: usize) -> Result<(), ProgramError> {
+    let slots_count = get_slots_count(data)? + n;
+    if get_layout(data)?.players_offset + slots_len(slots_count) > data.len() {
+        return Err(ProcessError::MalformedPlayersRegAccount)?;
+    }
+    set_slots_count(data, slots_count)
 }
 
 /// Add new player to the account. Return Some(index_of_the_player) if success.  If the player can't
 /// be added, the caller should realloc the account and retry.
+///
+/// For the versioned layout, the player's id must be greater than all ids in the account, which is
+/// the case as the access version only grows.
 pub fn add_player(data: &mut [u8], player: &PlayerJoin) -> Result<Option<usize>, ProgramError> {
     let slots_count = get_slots_count(&data)?;
+    let players_offset = get_layout(data)?.players_offset;
+    let (first_slot, empty_len) = if get_layout_version(data)? == LAYOUT_VERSION {
+        (slots_count - get_free_slots_count(data)?, PLAYER_INFO_LEN)
+    } else {
+        (0, PUBKEY_LEN)
+    };
     // Find a slot
-    for i in 0..slots_count {
-        let start = i * PLAYER_INFO_LEN + HEAD_LEN;
-        let addr_end = start + PUBKEY_LEN;
-        if data[start..addr_end].iter().all(|&n| n == 0) {
+    for i in first_slot..slots_count {
+        let start = i * PLAYER_INFO_LEN + players_offset;
+        if data[start..(start + empty_len)].iter().all(|&n| n == 0) {
             // Found an empty slot, increase the player acount and insert player info.
             increase_players_count(data)?;
             set_position_flag(data, player.position, true)?;
@@ -249,9 +491,43 @@ pub fn add_player(data: &mut [u8], player: &PlayerJoin) -> Result<Option<usize>,
     Ok(None) // Failed to insert
 }
 
+/// Pack the players to the front, in the same order, and drop the empty slots.  Return the new
+/// length of data, the caller should shrink the account to it.
+pub fn compact(data: &mut [u8]) -> Result<usize, ProgramError> {
+    let slots_count = get_slots_count(data)?;
+    let players_offset = get_layout(data)?.players_offset;
+    let mut players_count = 0;
+    for i in 0..slots_count {
+        let start = players_offset + i * PLAYER_INFO_LEN;
+        if data[start..(start + PUBKEY_LEN)].iter().all(|&n| n == 0) {
+            continue;
+        }
+        if players_count != i {
+            let dest = players_offset + players_count * PLAYER_INFO_LEN;
+            data.copy_within(start..(start + PLAYER_INFO_LEN), dest);
+        }
+        players_count += 1;
+    }
+    set_slots_count(data, players_count)?;
+    Ok(players_offset + slots_len(players_count))
+}
+
+/// Remove all players and slots, only the versions are kept.  The caller should resize the account
+/// to [`HEAD_LEN`] afterwards, it can be initialized again then.
+pub fn clear_players(data: &mut [u8]) -> Result<(), ProgramError> {
+    get_layout(data)?;
+    data[COUNT_OFFSET..].fill(0);
+    Ok(())
+}
+
+/// Remove the player at `index`.  For the versioned layout, the id is kept as a tombstone.
 pub fn remove_player_by_index(data: &mut [u8], index: usize) -> Result<(), ProgramError> {
-    let start = index * PLAYER_INFO_LEN + HEAD_LEN;
-    let end = start + PLAYER_INFO_LEN;
+    let start = index * PLAYER_INFO_LEN + get_layout(data)?.players_offset;
+    let end = if get_layout_version(data)? == LAYOUT_VERSION {
+        start + ID_OFFSET
+    } else {
+        start + PLAYER_INFO_LEN
+    };
     if &[0; 32] != &data[start..(start + PUBKEY_LEN)] {
         let pos_start = start + POSITION_OFFSET;
         let pos_end = pos_start + POSITION_LEN;
@@ -349,6 +625,26 @@ mod tests {
         assert_eq!(added_player.position, player.position);
     }
 
+    #[test]
+    fn test_add_slots() {
+        let player = create_player(Pubkey::new_unique(), 0, 1);
+        let mut data = setup_data(vec![player]);
+        assert_eq!(get_free_slots_count(&data).unwrap(), 0);
+        assert!(add_slots(&mut data, 2).is_err());
+
+        data.resize(data.len() + slots_len(2), 0);
+        add_slots(&mut data, 2).unwrap();
+        assert_eq!(get_slots_count(&data).unwrap(), 3);
+        assert_eq!(get_free_slots_count(&data).unwrap(), 2);
+
+        let player2 = create_player(Pubkey::new_unique(), 1, 2);
+        let player3 = create_player(Pubkey::new_unique(), 2, 3);
+        assert_eq!(add_player(&mut data, &player2).unwrap(), Some(1));
+        assert_eq!(add_player(&mut data, &player3).unwrap(), Some(2));
+        assert_eq!(add_player(&mut data, &player3).unwrap(), None);
+        assert_eq!(get_free_slots_count(&data).unwrap(), 0);
+    }
+
     #[test]
     fn test_remove_player_by_index() {
         let player = create_player(Pubkey::new_unique(), 1, 1);
@@ -361,6 +657,25 @@ mod tests {
         assert_eq!(is_position_occupied(&data, 1).unwrap(), false);
     }
 
+    #[test]
+    fn test_clear_players() {
+        let players = vec![
+            create_player(Pubkey::new_unique(), 0, 1),
+            create_player(Pubkey::new_unique(), 1, 2),
+        ];
+        let mut data = setup_data(players);
+        set_versions(&mut data, 2, 0).unwrap();
+        set_position_flag(&mut data, 0, true).unwrap();
+        set_position_flag(&mut data, 1, true).unwrap();
+        clear_players(&mut data).unwrap();
+        data.truncate(HEAD_LEN);
+        assert_eq!(get_players_count(&data).unwrap(), 0);
+        assert_eq!(get_slots_count(&data).unwrap(), 0);
+        assert_eq!(is_position_occupied(&data, 0).unwrap(), false);
+        assert_eq!(is_position_occupied(&data, 1).unwrap(), false);
+        assert_eq!(u64::try_from_slice(&data[ACCESS_VERSION_OFFSET..SETTLE_VERSION_OFFSET]).unwrap(), 2);
+    }
+
     #[test]
     fn test_set_position_flag() {
         let mut data = vec![0; HEAD_LEN];
@@ -385,6 +700,29 @@ mod tests {
         assert_eq!(is_position_occupied(&data, 1).unwrap(), false);
     }
 
+    #[test]
+    fn test_change_and_swap_positions() {
+        let players = vec![
+            create_player(Pubkey::new_unique(), 0, 1),
+            create_player(Pubkey::new_unique(), 1, 2),
+        ];
+        let mut data = setup_data_v2(4, players.clone());
+
+        assert!(change_position(&mut data, 0, 1).is_err());
+        assert!(change_position(&mut data, 2, 3).is_err());
+        change_position(&mut data, 0, 3).unwrap();
+        assert_eq!(get_player_by_index(&data, 0).unwrap().unwrap().position, 3);
+        assert_eq!(is_position_occupied(&data, 0).unwrap(), false);
+        assert_eq!(is_position_occupied(&data, 3).unwrap(), true);
+
+        swap_positions(&mut data, 0, 1).unwrap();
+        assert_eq!(get_player_by_index(&data, 0).unwrap().unwrap().position, 1);
+        assert_eq!(get_player_by_index(&data, 1).unwrap().unwrap().position, 3);
+        assert_eq!(is_position_occupied(&data, 1).unwrap(), true);
+        assert_eq!(is_position_occupied(&data, 3).unwrap(), true);
+        assert_eq!(get_player_by_id(&data, 2).unwrap().unwrap().1.addr, players[1].addr);
+    }
+
     #[test]
     fn test_get_available_position() {
         let mut data = vec![0; HEAD_LEN + PLAYER_INFO_LEN * 2]; // Assume two players for simplicity
@@ -408,4 +746,130 @@ mod tests {
         assert_eq!(index, 0);
         assert_eq!(found_player.position, player.position);
     }
+
+    fn setup_data_v2(max_players: u16, players: Vec<PlayerJoin>) -> Vec<u8> {
+        let mut data = vec![0; head_len(max_players)];
+        init_account_data(&mut data, max_players).unwrap();
+        data.resize(data.len() + slots_len(players.len()), 0);
+        add_slots(&mut data, players.len()).unwrap();
+        for player in players.iter() {
+            add_player(&mut data, player).unwrap().unwrap();
+        }
+        data
+    }
+
+    #[test]
+    fn test_init_account_data_v2() {
+        let data = setup_data_v2(2000, vec![]);
+        assert_eq!(data.len(), 32 + 250);
+        assert_eq!(get_layout_version(&data).unwrap(), LAYOUT_VERSION);
+        assert_eq!(get_players_count(&data).unwrap(), 0);
+        assert_eq!(get_slots_count(&data).unwrap(), 0);
+        assert_eq!(is_position_occupied(&data, 1999).unwrap(), false);
+        assert_eq!(is_position_occupied(&data, 2000).unwrap(), true);
+        assert!(set_position_flag(&mut data.clone(), 2000, true).is_err());
+    }
+
+    #[test]
+    fn test_players_beyond_1024_v2() {
+        let player1 = create_player(Pubkey::new_unique(), 1500, 1);
+        let player2 = create_player(Pubkey::new_unique(), 0, 2);
+        let mut data = setup_data_v2(2000, vec![player1.clone(), player2.clone()]);
+        assert_eq!(get_players_count(&data).unwrap(), 2);
+        assert_eq!(is_position_occupied(&data, 1500).unwrap(), true);
+        assert_eq!(get_available_position(&data, 2000).unwrap(), 1);
+        assert!(is_player_joined(&data, &player1.addr).unwrap());
+
+        let (index, found) = get_player_by_id(&data, 2).unwrap().unwrap();
+        assert_eq!(index, 1);
+        assert_eq!(found.addr, player2.addr);
+
+        // Append a slot like join does
+        let player3 = create_player(Pubkey::new_unique(), 1999, 3);
+        assert_eq!(add_player(&mut data, &player3).unwrap(), None);
+        increase_size_set_position_flag(&mut data, player3.position).unwrap();
+        increase_slots_count(&mut data).unwrap();
+        data.extend(borsh::to_vec(&player3).unwrap());
+        assert_eq!(get_player_by_addr(&data, &player3.addr).unwrap().unwrap().0, 2);
+        assert_eq!(get_players_count(&data).unwrap(), 3);
+
+        remove_player_by_index(&mut data, 0).unwrap();
+        assert_eq!(is_position_occupied(&data, 1500).unwrap(), false);
+        assert_eq!(get_players_count(&data).unwrap(), 2);
+        assert_eq!(get_free_slots_count(&data).unwrap(), 0);
+        assert!(get_player_by_id(&data, 1).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_get_player_by_id_sorted() {
+        let players: Vec<PlayerJoin> = (0..5).map(|i| create_player(Pubkey::new_unique(), i, i as u64 * 2 + 1)).collect();
+        let mut data = setup_data_v2(8, players.clone());
+        data.resize(data.len() + slots_len(2), 0);
+        add_slots(&mut data, 2).unwrap();
+        assert_eq!(get_free_slots_count(&data).unwrap(), 2);
+
+        for (i, player) in players.iter().enumerate() {
+            let (index, found) = get_player_by_id(&data, player.access_version).unwrap().unwrap();
+            assert_eq!(index, i);
+            assert_eq!(found.addr, player.addr);
+        }
+        assert!(get_player_by_id(&data, 0).unwrap().is_none());
+        assert!(get_player_by_id(&data, 4).unwrap().is_none());
+        assert!(get_player_by_id(&data, 100).unwrap().is_none());
+
+        // A removed player leaves a tombstone, its slot is not reused
+        remove_player_by_index(&mut data, 2).unwrap();
+        assert!(get_player_by_id(&data, 5).unwrap().is_none());
+        assert_eq!(get_player_by_id(&data, 7).unwrap().unwrap().0, 3);
+        assert_eq!(get_free_slots_count(&data).unwrap(), 2);
+        let player = create_player(Pubkey::new_unique(), 2, 11);
+        assert_eq!(add_player(&mut data, &player).unwrap(), Some(5));
+        assert_eq!(get_player_by_id(&data, 11).unwrap().unwrap().1.addr, player.addr);
+        assert_eq!(get_free_slots_count(&data).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_migrate_legacy() {
+        let player1 = create_player(Pubkey::new_unique(), 3, 1);
+        let player2 = create_player(Pubkey::new_unique(), 5, 2);
+        let mut data = setup_data(vec![player1.clone(), player2.clone()]);
+        set_versions(&mut data, 2, 1).unwrap();
+        remove_player_by_index(&mut data, 0).unwrap();
+
+        let new_data = migrate_legacy(&data, 6).unwrap();
+        assert_eq!(new_data.len(), head_len(6) + slots_len(1));
+        assert_eq!(get_layout_version(&new_data).unwrap(), LAYOUT_VERSION);
+        assert_eq!(new_data[..COUNT_OFFSET], data[..COUNT_OFFSET]);
+        assert_eq!(get_players_count(&new_data).unwrap(), 1);
+        assert_eq!(get_slots_count(&new_data).unwrap(), 1);
+        assert_eq!(get_free_slots_count(&new_data).unwrap(), 0);
+        assert_eq!(is_position_occupied(&new_data, 3).unwrap(), false);
+        assert_eq!(is_position_occupied(&new_data, 5).unwrap(), true);
+        assert_eq!(get_player_by_index(&new_data, 0).unwrap().unwrap().addr, player2.addr);
+
+        assert!(migrate_legacy(&new_data, 6).is_err());
+    }
+
+    #[test]
+    fn test_compact() {
+        let players: Vec<PlayerJoin> = (0..4).map(|i| create_player(Pubkey::new_unique(), i, i as u64 + 1)).collect();
+        let mut data = setup_data_v2(4, players.clone());
+        remove_player_by_index(&mut data, 0).unwrap();
+        remove_player_by_index(&mut data, 2).unwrap();
+
+        let new_len = compact(&mut data).unwrap();
+        data.truncate(new_len);
+        assert_eq!(data.len(), head_len(4) + slots_len(2));
+        assert_eq!(get_slots_count(&data).unwrap(), 2);
+        assert_eq!(get_players_count(&data).unwrap(), 2);
+        assert_eq!(get_player_by_id(&data, 2).unwrap().unwrap().1.addr, players[1].addr);
+        assert_eq!(get_player_by_id(&data, 4).unwrap().unwrap().0, 1);
+        assert_eq!(is_position_occupied(&data, 3).unwrap(), true);
+        assert_eq!(is_position_occupied(&data, 2).unwrap(), false);
+
+        let mut legacy_data = setup_data(players.clone());
+        remove_player_by_index(&mut legacy_data, 1).unwrap();
+        assert_eq!(compact(&mut legacy_data).unwrap(), HEAD_LEN + slots_len(3));
+        assert_eq!(get_player_by_index(&legacy_data, 1).unwrap().unwrap().addr, players[2].addr);
+    }
 }
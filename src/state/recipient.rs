@@ -37,6 +37,22 @@ pub struct RecipientSlot {
     pub shares: Vec<RecipientSlotShare>,
 }
 
+impl RecipientSlot {
+    /// Check if `amount` can be paid back out of the `stake_amount` held by the slot, leaving
+    /// every share with no more claimed than its part of the rest.
+    pub fn can_pay_back(&self, stake_amount: u64, amount: u64) -> bool {
+        let Some(stake_amount) = stake_amount.checked_sub(amount) else {
+            return false;
+        };
+        let total_weights: u64 = self.shares.iter().map(|s| s.weights as u64).sum();
+        let total_amount: u64 = self.shares.iter().map(|s| s.claim_amount).sum::<u64>() + stake_amount;
+
+        self.shares
+            .iter()
+            .all(|s| total_weights > 0 && s.claim_amount <= total_amount * s.weights as u64 / total_weights)
+    }
+}
+
 // State of on-chain RecipientAccount
 #[cfg_attr(test, derive(PartialEq, Clone))]
 #[derive(BorshDeserialize, BorshSerialize, Debug)]
@@ -51,3 +67,38 @@ impl IsInitialized for RecipientState {
         self.is_initialized
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slot(claim_amounts: &[u64]) -> RecipientSlot {
+        RecipientSlot {
+            id: 0,
+            slot_type: RecipientSlotType::Token,
+            token_addr: Pubkey::new_unique(),
+            stake_addr: Pubkey::new_unique(),
+            shares: claim_amounts
+                .iter()
+                .map(|claim_amount| RecipientSlotShare {
+                    owner: RecipientSlotOwner::Assigned { addr: Pubkey::new_unique() },
+                    weights: 1,
+                    claim_amount: *claim_amount,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_can_pay_back() {
+        // Nothing claimed, any amount held can go back
+        assert!(slot(&[0, 0]).can_pay_back(100, 100));
+        assert!(!slot(&[0, 0]).can_pay_back(100, 101));
+        // Total is 100, the first share claimed its 50
+        assert!(slot(&[50, 0]).can_pay_back(50, 0));
+        assert!(!slot(&[50, 0]).can_pay_back(50, 1));
+        // Total is 100, the first share claimed 30 out of its 50
+        assert!(slot(&[30, 0]).can_pay_back(70, 40));
+        assert!(!slot(&[30, 0]).can_pay_back(70, 41));
+    }
+}
@@ -0,0 +1,70 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    program_pack::IsInitialized,
+    pubkey::Pubkey,
+};
+
+use crate::constants::TICKET_SEED;
+
+// State of on-chain TicketAccount, an entry into a ticket game awarded by a satellite.  It's the
+// PDA of the game and the player, and is closed when redeemed by joining the game, or refunded
+// once it expires.
+#[cfg_attr(test, derive(PartialEq, Clone))]
+#[derive(Default, BorshDeserialize, BorshSerialize, Debug)]
+pub struct TicketState {
+    pub is_initialized: bool,
+    // the game to join with this ticket
+    pub game_addr: Pubkey,
+    // the player who can redeem this ticket
+    pub owner: Pubkey,
+    // the satellite game which awarded this ticket
+    pub source_game_addr: Pubkey,
+    // the value held for this ticket in the stake account of the game
+    pub amount: u64,
+    // who paid the rent of this account, it's refunded when the ticket is redeemed
+    pub rent_payer: Pubkey,
+    // the tournament of the game, its stake account holds the value instead
+    pub tournament: Option<Pubkey>,
+}
+
+impl TicketState {
+    /// The address of the ticket of `player` for `game`, one unredeemed ticket for each.
+    pub fn find_address(program_id: &Pubkey, game: &Pubkey, player: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[TICKET_SEED.as_bytes(), game.as_ref(), player.as_ref()], program_id)
+    }
+}
+
+impl IsInitialized for TicketState {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_address() {
+        let program_id = Pubkey::new_unique();
+        let game = Pubkey::new_unique();
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+
+        // The PDA of the ticket seed, the game and the player, off the curve
+        let (alice_ticket, bump) = TicketState::find_address(&program_id, &game, &alice);
+        let seeds: &[&[u8]] = &[TICKET_SEED.as_bytes(), game.as_ref(), alice.as_ref(), &[bump]];
+        assert_eq!(Pubkey::create_program_address(seeds, &program_id).unwrap(), alice_ticket);
+        assert!(!alice_ticket.is_on_curve());
+
+        // Without the seed prefix, it would collide with other PDAs of the game
+        let (unprefixed, _) = Pubkey::find_program_address(&[game.as_ref(), alice.as_ref()], &program_id);
+        assert_ne!(unprefixed, alice_ticket);
+
+        // One ticket per game and player, under this program only
+        assert_ne!(TicketState::find_address(&program_id, &game, &bob).0, alice_ticket);
+        assert_ne!(TicketState::find_address(&program_id, &Pubkey::new_unique(), &alice).0, alice_ticket);
+        assert_ne!(TicketState::find_address(&Pubkey::new_unique(), &game, &alice).0, alice_ticket);
+        assert_ne!(TicketState::find_address(&program_id, &alice, &game).0, alice_ticket);
+    }
+}
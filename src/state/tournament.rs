@@ -0,0 +1,197 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    msg,
+    program_error::ProgramError,
+    program_pack::IsInitialized,
+    pubkey::Pubkey,
+};
+
+use crate::{error::ProcessError, types::TournamentPrize};
+
+use super::EntryType;
+
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[derive(Default, BorshDeserialize, BorshSerialize, Clone, Debug)]
+pub struct TournamentPlayer {
+    pub addr: Pubkey,
+    // the game where the player sits
+    pub game_addr: Pubkey,
+}
+
+// State of on-chain TournamentAccount
+#[cfg_attr(test, derive(PartialEq, Clone))]
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct TournamentState {
+    pub is_initialized: bool,
+    // set once the prizes are paid, no more joins are accepted
+    pub is_finished: bool,
+    pub title: String,
+    pub owner: Pubkey,
+    // mint id of the token used for all games
+    pub token_mint: Pubkey,
+    // addr to the account that holds the entries of all games
+    pub stake_account: Pubkey,
+    // the entry type shared by all games
+    pub entry_type: EntryType,
+    // the schedule shared by all games
+    pub start_time: Option<u64>,
+    pub registration_close_time: Option<u64>,
+    pub late_registration_until: Option<u64>,
+    // the linked games
+    pub games: Vec<Pubkey>,
+    // the final table set by the owner, its transactor pays the prizes
+    pub final_game: Option<Pubkey>,
+    // all players ever joined, with the game they sit at
+    pub players: Vec<TournamentPlayer>,
+    // the sum of entries received by the stake account
+    pub prize_pool: u64,
+    // the value of satellite tickets into the games, held by the stake account until redeemed
+    pub ticket_funds: u64,
+}
+
+impl TournamentState {
+    /// A tournament accepts joins and deposits until its prizes are paid.
+    pub fn is_open(&self) -> bool {
+        self.is_initialized && !self.is_finished
+    }
+
+    pub fn get_player_mut(&mut self, addr: &Pubkey) -> Option<&mut TournamentPlayer> {
+        self.players.iter_mut().find(|p| p.addr.eq(addr))
+    }
+
+    /// Seat a player at `game_addr`.  A player enters the tournament once, rebuys are made with
+    /// deposits.
+    pub fn add_player(&mut self, addr: &Pubkey, game_addr: &Pubkey) -> Result<(), ProgramError> {
+        if self.players.iter().any(|p| p.addr.eq(addr)) {
+            msg!("Player {} entered the tournament already", addr);
+            return Err(ProcessError::JoinedGameAlready)?;
+        }
+        self.players.push(TournamentPlayer {
+            addr: *addr,
+            game_addr: *game_addr,
+        });
+        Ok(())
+    }
+
+    /// Remove a player whose entry is refunded.
+    pub fn remove_player(&mut self, addr: &Pubkey) {
+        self.players.retain(|p| p.addr.ne(addr));
+    }
+
+    /// Take a refunded entry out of the prize pool.
+    pub fn refund(&mut self, amount: u64) -> Result<(), ProgramError> {
+        self.prize_pool = self
+            .prize_pool
+            .checked_sub(amount)
+            .ok_or(ProcessError::InvalidTournament)?;
+        Ok(())
+    }
+
+    /// Validate the final prizes, every winner must have entered the tournament and the prizes
+    /// can't exceed the prize pool.  Return the sum of prizes.
+    pub fn validate_prizes(&self, prizes: &[TournamentPrize]) -> Result<u64, ProgramError> {
+        let total = prizes
+            .iter()
+            .try_fold(0u64, |sum, p| sum.checked_add(p.amount))
+            .ok_or(ProcessError::StakeAmountOverflow)?;
+
+        if total > self.prize_pool {
+            msg!("Prizes {} exceed the prize pool {}", total, self.prize_pool);
+            return Err(ProcessError::InvalidSettleAmounts)?;
+        }
+
+        if let Some(prize) = prizes.iter().find(|prize| self.players.iter().all(|p| p.addr.ne(&prize.addr))) {
+            msg!("Player {} is not in the tournament", prize.addr);
+            return Err(ProcessError::PlayerNotInGame)?;
+        }
+
+        Ok(total)
+    }
+}
+
+impl IsInitialized for TournamentState {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tournament(players: &[Pubkey], prize_pool: u64) -> TournamentState {
+        let game_addr = Pubkey::new_unique();
+        TournamentState {
+            is_initialized: true,
+            is_finished: false,
+            title: "MTT".to_string(),
+            owner: Pubkey::new_unique(),
+            token_mint: Pubkey::new_unique(),
+            stake_account: Pubkey::new_unique(),
+            entry_type: EntryType::Free,
+            start_time: None,
+            registration_close_time: None,
+            late_registration_until: None,
+            games: vec![game_addr],
+            final_game: None,
+            players: players
+                .iter()
+                .map(|addr| TournamentPlayer { addr: *addr, game_addr })
+                .collect(),
+            prize_pool,
+            ticket_funds: 0,
+        }
+    }
+
+    fn prize(addr: Pubkey, amount: u64) -> TournamentPrize {
+        TournamentPrize { addr, amount }
+    }
+
+    #[test]
+    fn test_validate_prizes() {
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        let tournament_state = tournament(&[alice, bob], 300);
+
+        assert_eq!(tournament_state.validate_prizes(&[prize(alice, 200), prize(bob, 100)]).unwrap(), 300);
+        assert_eq!(tournament_state.validate_prizes(&[prize(alice, 250)]).unwrap(), 250);
+        assert_eq!(tournament_state.validate_prizes(&[]).unwrap(), 0);
+
+        // Prizes exceed the pool
+        assert!(tournament_state.validate_prizes(&[prize(alice, 200), prize(bob, 101)]).is_err());
+        // Prizes overflow
+        assert!(tournament_state.validate_prizes(&[prize(alice, u64::MAX), prize(bob, 2)]).is_err());
+        // Winner never entered
+        assert!(tournament_state.validate_prizes(&[prize(Pubkey::new_unique(), 1)]).is_err());
+    }
+
+    #[test]
+    fn test_add_and_remove_player() {
+        let alice = Pubkey::new_unique();
+        let game_addr = Pubkey::new_unique();
+        let mut tournament_state = tournament(&[], 100);
+
+        tournament_state.add_player(&alice, &game_addr).unwrap();
+        assert!(tournament_state.add_player(&alice, &Pubkey::new_unique()).is_err());
+        assert_eq!(tournament_state.get_player_mut(&alice).unwrap().game_addr, game_addr);
+
+        tournament_state.refund(40).unwrap();
+        assert_eq!(tournament_state.prize_pool, 60);
+        assert!(tournament_state.refund(61).is_err());
+
+        tournament_state.remove_player(&alice);
+        assert!(tournament_state.players.is_empty());
+        assert!(tournament_state.validate_prizes(&[prize(alice, 1)]).is_err());
+    }
+
+    #[test]
+    fn test_is_open() {
+        let mut tournament_state = tournament(&[], 0);
+        assert!(tournament_state.is_open());
+        tournament_state.is_finished = true;
+        assert!(!tournament_state.is_open());
+        tournament_state.is_finished = false;
+        tournament_state.is_initialized = false;
+        assert!(!tournament_state.is_open());
+    }
+}
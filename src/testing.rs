@@ -0,0 +1,274 @@
+//! Helpers for the tests of instruction processors.
+//!
+//! Accounts are serialized in the same layout the runtime passes to the entrypoint, so the
+//! processors run unchanged, reallocs included.  Rent, clock and the system program are stubbed,
+//! token programs are not available, thus tests use games of the native mint.
+
+use std::{collections::HashMap, mem::size_of, sync::Once};
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::{deserialize, ProgramResult, MAX_PERMITTED_DATA_INCREASE, NON_DUP_MARKER, SUCCESS},
+    instruction::Instruction,
+    program_error::ProgramError,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    program_utils::limited_deserialize,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction::SystemInstruction,
+    system_program,
+};
+
+use crate::{
+    instruction::RaceInstruction,
+    processor,
+    state::{players, EntryType, GameState, GameStatus, PlayerJoin, RebuyRules, TournamentPlayer, TournamentState},
+};
+
+struct TestSyscalls;
+
+impl SyscallStubs for TestSyscalls {
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Clock) = Clock::default() };
+        SUCCESS
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        if instruction.program_id.ne(&system_program::id()) {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let find = |key: &Pubkey| {
+            account_infos
+                .iter()
+                .find(|a| a.key.eq(key))
+                .ok_or(ProgramError::NotEnoughAccountKeys)
+        };
+        let move_lamports = |from: &AccountInfo, to: &AccountInfo, lamports: u64| -> ProgramResult {
+            let from_lamports = from.lamports().checked_sub(lamports).ok_or(ProgramError::InsufficientFunds)?;
+            **from.lamports.borrow_mut() = from_lamports;
+            **to.lamports.borrow_mut() += lamports;
+            Ok(())
+        };
+        let system_ix = limited_deserialize(&instruction.data, 1024).map_err(|_| ProgramError::InvalidInstructionData)?;
+        match system_ix {
+            SystemInstruction::Transfer { lamports } => {
+                let from = find(&instruction.accounts[0].pubkey)?;
+                let to = find(&instruction.accounts[1].pubkey)?;
+                move_lamports(from, to, lamports)
+            }
+            SystemInstruction::CreateAccount { lamports, space, owner } => {
+                let from = find(&instruction.accounts[0].pubkey)?;
+                let to = find(&instruction.accounts[1].pubkey)?;
+                // Like the system program, an address holding lamports is in use already
+                if to.lamports() > 0 {
+                    return Err(ProgramError::AccountAlreadyInitialized);
+                }
+                move_lamports(from, to, lamports)?;
+                to.realloc(space as usize, true)?;
+                to.assign(&owner);
+                Ok(())
+            }
+            SystemInstruction::Allocate { space } => find(&instruction.accounts[0].pubkey)?.realloc(space as usize, true),
+            SystemInstruction::Assign { owner } => {
+                find(&instruction.accounts[0].pubkey)?.assign(&owner);
+                Ok(())
+            }
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+}
+
+static INIT: Once = Once::new();
+
+#[derive(Clone, Debug)]
+pub struct TestAccount {
+    pub key: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub is_signer: bool,
+}
+
+impl TestAccount {
+    pub fn new(owner: &Pubkey, lamports: u64, data: Vec<u8>) -> Self {
+        TestAccount {
+            key: Pubkey::new_unique(),
+            owner: *owner,
+            lamports,
+            data,
+            is_signer: false,
+        }
+    }
+
+    /// A system account holding `lamports`, e.g. a wallet.
+    pub fn wallet(lamports: u64) -> Self {
+        TestAccount::new(&system_program::id(), lamports, vec![])
+    }
+
+    /// An account owned by the program, funded for rent-exempt.
+    pub fn program_account(program_id: &Pubkey, data: Vec<u8>) -> Self {
+        let lamports = Rent::default().minimum_balance(data.len());
+        TestAccount::new(program_id, lamports, data)
+    }
+
+    /// The system program, passed to the processors which may resize accounts.
+    pub fn system_program() -> Self {
+        TestAccount::new(&Pubkey::default(), 1, vec![]).with_key(system_program::id())
+    }
+
+    pub fn with_key(mut self, key: Pubkey) -> Self {
+        self.key = key;
+        self
+    }
+
+    pub fn signer(mut self) -> Self {
+        self.is_signer = true;
+        self
+    }
+
+    pub fn state<T: BorshDeserialize>(&self) -> T {
+        T::try_from_slice(&self.data).unwrap()
+    }
+
+    pub fn game_state(&self) -> GameState {
+        GameState::unpack(&self.data).unwrap()
+    }
+}
+
+/// Run `instruction` with `accounts`, in order.  An account given more than once, by key, is
+/// passed as a duplicate.  Like the runtime, the accounts are only updated when it succeeds.
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &mut [&mut TestAccount],
+    instruction: RaceInstruction,
+) -> ProgramResult {
+    INIT.call_once(|| {
+        set_syscall_stubs(Box::new(TestSyscalls));
+    });
+
+    let instruction_data = borsh::to_vec(&instruction).unwrap();
+    let mut input = serialize(program_id, accounts, &instruction_data);
+    let (program_id, account_infos, instruction_data) = unsafe { deserialize(input.as_mut_ptr() as *mut u8) };
+
+    processor::process(program_id, &account_infos, instruction_data)?;
+
+    for account in accounts.iter_mut() {
+        let info = account_infos.iter().find(|info| info.key.eq(&account.key)).unwrap();
+        account.owner = *info.owner;
+        account.lamports = info.lamports();
+        account.data = info.data.borrow().to_vec();
+    }
+    Ok(())
+}
+
+/// Serialize the input of the entrypoint, into u64 words for the alignment.
+fn serialize(program_id: &Pubkey, accounts: &[&mut TestAccount], instruction_data: &[u8]) -> Vec<u64> {
+    let mut bytes: Vec<u8> = Vec::new();
+    bytes.extend_from_slice(&(accounts.len() as u64).to_le_bytes());
+
+    let mut indexes: HashMap<Pubkey, usize> = HashMap::new();
+    for (i, account) in accounts.iter().enumerate() {
+        if let Some(index) = indexes.get(&account.key) {
+            bytes.push(*index as u8);
+            bytes.extend_from_slice(&[0; 7]);
+            continue;
+        }
+        indexes.insert(account.key, i);
+        bytes.push(NON_DUP_MARKER);
+        bytes.push(account.is_signer as u8);
+        bytes.push(1);
+        bytes.push(0);
+        bytes.extend_from_slice(&[0; 4]);
+        bytes.extend_from_slice(account.key.as_ref());
+        bytes.extend_from_slice(account.owner.as_ref());
+        bytes.extend_from_slice(&account.lamports.to_le_bytes());
+        bytes.extend_from_slice(&(account.data.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&account.data);
+        bytes.resize(bytes.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+        bytes.resize(bytes.len().next_multiple_of(size_of::<u64>()), 0);
+        bytes.extend_from_slice(&0u64.to_le_bytes());
+    }
+
+    bytes.extend_from_slice(&(instruction_data.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(instruction_data);
+    bytes.extend_from_slice(program_id.as_ref());
+
+    bytes
+        .chunks(size_of::<u64>())
+        .map(|chunk| {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            u64::from_le_bytes(word)
+        })
+        .collect()
+}
+
+/// A cash game of the native mint.
+pub fn cash_game(min_deposit: u64, max_deposit: u64) -> GameState {
+    GameState {
+        game_status: GameStatus::Initialized,
+        entry_type: EntryType::Cash { min_deposit, max_deposit, deposit_step: None, table_stakes: false },
+        token_mint: spl_token::native_mint::id(),
+        max_players: 6,
+        ..Default::default()
+    }
+}
+
+/// A ticket game of the native mint.
+pub fn ticket_game(amount: u64, rebuy: Option<RebuyRules>, fee: Option<u64>) -> GameState {
+    GameState {
+        game_status: GameStatus::Initialized,
+        entry_type: EntryType::Ticket { amount, rebuy, fee },
+        token_mint: spl_token::native_mint::id(),
+        max_players: 6,
+        ..Default::default()
+    }
+}
+
+/// The data of a versioned players reg account with `players` seated.
+pub fn players_reg_data(max_players: u16, players: &[PlayerJoin]) -> Vec<u8> {
+    let mut data = vec![0; players::head_len(max_players)];
+    players::init_account_data(&mut data, max_players).unwrap();
+    for player in players.iter() {
+        data.resize(data.len() + players::slots_len(1), 0);
+        players::add_slots(&mut data, 1).unwrap();
+        players::add_player(&mut data, player).unwrap();
+    }
+    data
+}
+
+/// An open tournament of `games`, with `players` seated at the first game.
+pub fn tournament_state(games: &[Pubkey], players: &[Pubkey], prize_pool: u64) -> TournamentState {
+    TournamentState {
+        is_initialized: true,
+        is_finished: false,
+        title: "MTT".to_string(),
+        owner: Pubkey::new_unique(),
+        token_mint: spl_token::native_mint::id(),
+        stake_account: Pubkey::new_unique(),
+        entry_type: EntryType::Ticket { amount: 100, rebuy: None, fee: None },
+        start_time: None,
+        registration_close_time: None,
+        late_registration_until: None,
+        games: games.to_vec(),
+        final_game: None,
+        players: players
+            .iter()
+            .map(|addr| TournamentPlayer { addr: *addr, game_addr: games[0] })
+            .collect(),
+        prize_pool,
+        ticket_funds: 0,
+    }
+}
@@ -58,6 +58,11 @@ pub struct CreateGameAccountParams {
     pub max_players: u16,
     pub entry_type: EntryType,
     pub data: Vec<u8>,
+    pub start_time: Option<u64>,
+    pub registration_close_time: Option<u64>,
+    pub late_registration_until: Option<u64>,
+    pub invite_key: Option<Pubkey>,
+    pub gatekeeper: Option<Pubkey>,
 }
 
 #[derive(Debug, BorshSerialize, BorshDeserialize, Clone)]
@@ -140,6 +145,13 @@ pub struct Award {
     pub bonus_identifier: String,
 }
 
+/// Award the player an entry into another ticket game, paid from the stake of this game.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct TicketAward {
+    pub player_id: u64,
+    pub game_addr: Pubkey,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
 pub struct SettleParams {
     pub settles: Box<Vec<Settle>>,
@@ -151,6 +163,7 @@ pub struct SettleParams {
     pub next_settle_version: u64,
     pub entry_lock: Option<EntryLock>,
     pub accept_deposits: Box<Vec<u64>>,
+    pub ticket_awards: Box<Vec<TicketAward>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
@@ -159,6 +172,24 @@ pub struct JoinParams {
     pub access_version: u64,
     pub settle_version: u64,
     pub position: u16,
+    // Merkle proof of the player, for EntryType::Allowlist
+    pub proof: Vec<[u8; 32]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct BatchJoinPlayer {
+    pub addr: Pubkey,
+    pub position: u16,
+    // Merkle proof of the player, for EntryType::Allowlist when the transactor signs
+    pub proof: Vec<[u8; 32]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct BatchJoinParams {
+    // The amount paid for each player
+    pub amount: u64,
+    pub settle_version: u64,
+    pub players: Vec<BatchJoinPlayer>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
@@ -209,3 +240,60 @@ pub struct AttachBonusParams {
 pub struct RejectDepositsParams {
     pub reject_deposits: Vec<u64>,
 }
+
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct UpdateAllowlistParams {
+    pub merkle_root: [u8; 32],
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct SetGatekeeperParams {
+    pub gatekeeper: Option<Pubkey>,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct SponsorGameParams {
+    pub amount: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct ChangeSeatParams {
+    pub position: u16,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct SwapSeatsParams {
+    pub player_id: u64,
+    pub other_player_id: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct KickPlayerParams {
+    pub player_id: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct MoveBalanceParams {
+    pub player_id: u64,
+    pub position: u16,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct CreateTournamentParams {
+    pub title: String,
+    pub entry_type: EntryType,
+    pub start_time: Option<u64>,
+    pub registration_close_time: Option<u64>,
+    pub late_registration_until: Option<u64>,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct TournamentPrize {
+    pub addr: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(Debug, BorshSerialize, BorshDeserialize)]
+pub struct SettleTournamentParams {
+    pub prizes: Vec<TournamentPrize>,
+}